@@ -13,30 +13,52 @@ impl DenseLayer {
         DenseLayer { weights, biases }
     }
 
+    // e.g. `np.savez(path, weights=layer.kernel.numpy(), biases=layer.bias.numpy())`
+    fn load(path: &str) -> rural::Result<Self> {
+        let arrays = Matrix::read_npz(std::fs::File::open(path)?)?;
+        let array = |name: &str| {
+            arrays
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, matrix)| matrix.clone())
+                .ok_or_else(|| rural::Error::InvalidArgument(format!("missing '{name}' in {path}")))
+        };
+
+        Ok(DenseLayer {
+            weights: array("weights")?,
+            biases: array("biases")?,
+        })
+    }
+
+    // input: (batch_size, input_size), biases are broadcast over the batch
     fn forward(&self, input: &Matrix) -> Matrix {
-        input.matmul(&self.weights).add(&self.biases)
+        &input.matmul(&self.weights) + &self.biases
     }
 }
 
 fn main() {
-    let input_size = 2;
-    let output_size = 3;
+    let batch_size = 4;
 
-    let layer = DenseLayer::new(input_size, output_size);
+    // weights exported from NumPy, or random ones
+    let layer = match std::env::args().nth(1) {
+        Some(path) => DenseLayer::load(&path).expect("Failed to load the layer"),
+        None => DenseLayer::new(2, 3),
+    };
+    let (input_size, output_size) = layer.weights.shape();
 
-    let input = Matrix::random(1, input_size, 0);
-    assert_eq!(input.shape(), (1, input_size));
+    let input = Matrix::random(batch_size, input_size, 0);
+    assert_eq!(input.shape(), (batch_size, input_size));
 
     let output = layer.forward(&input);
-    assert_eq!(output.shape(), (1, output_size));
+    assert_eq!(output.shape(), (batch_size, output_size));
+
+    // each row of the batch matches a single-row forward pass
+    for i in 0..batch_size {
+        let row = layer.forward(&input.slice(i..=i, ..).to_matrix());
+        assert_eq!(row[0], output[i]);
+    }
 
     println!("layer: {:?}", layer);
     println!("input: {:?}", input);
     println!("output: {:?}", output);
 }
-
-// <tf.Tensor: shape=(1, 3), dtype=float32, numpy=array([[1.3372822, 2.3078232, 1.172242 ]], dtype=float32)>
-//
-// layer: DenseLayer { weights: Matrix { data: [0.7385979, 0.1139881, 0.611827, 0.2235182, 0.6560209, 0.16295183], shape: (2, 3) }, biases: Matrix { data: [0.15164787, 0.8817933, 0.23451138], shape: (1, 3) } }
-// input: Matrix { data: [1.0, 2.0], shape: (1, 2) }
-// output: Matrix { data: [1.3372822, 2.3078232, 1.172242], shape: (1, 3) }
@@ -0,0 +1,133 @@
+use plotters::prelude::*;
+use rand_core::SeedableRng as _;
+use rural::matrix::{Matrix, Rank, SvdOptions};
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+fn timed<R>(f: impl Fn() -> R) -> (R, Duration) {
+    const I: u64 = 16;
+
+    let mut i = 0;
+    let mut dt = 0;
+
+    loop {
+        let t0 = Instant::now();
+        let ret = f();
+
+        dt += Instant::now().duration_since(t0).as_nanos();
+
+        if i >= I {
+            return (ret, Duration::from_nanos((dt / I as u128) as u64));
+        }
+
+        i += 1;
+    }
+}
+
+// weights with a decaying spectrum (trained weights are far from 'white' random matrices)
+fn weights(size: usize) -> Matrix {
+    let mut u = Matrix::random(size, size, 1);
+    for i in 0..size {
+        for j in 0..size {
+            u[(i, j)] *= 0.95f32.powi(j as i32);
+        }
+    }
+
+    u.matmul(&Matrix::random(size, size, 2))
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let root = SVGBackend::new("target/plots-lowrank.svg", (800, 800)).into_drawing_area();
+
+    let mut cc = ChartBuilder::on(&root)
+        .margin(5)
+        .set_all_label_area_size(50)
+        .caption("low-rank matmul", ("sans-serif", 20))
+        .build_cartesian_2d(0f32..1., 0f32..10.)?;
+
+    let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+
+    for (size, color) in [
+        (32, RGBColor(0, 0, 0)),
+        (64, RGBColor(200, 0, 0)),
+        (128, RGBColor(0, 200, 0)),
+        (256, RGBColor(0, 0, 200)),
+        (512, RGBColor(200, 100, 0)),
+    ] {
+        let batch_size = 8;
+
+        let a = Matrix::random(batch_size, size, 0);
+        let w = weights(size);
+
+        let (expected, dt) = timed(|| a.matmul(&w));
+        println!("M: size={size} dt={dt:?}");
+
+        let mut measurements = vec![];
+
+        let n = 100;
+        for factor in [2, 5, 10, 25, 50] {
+            let factor = factor as f32 / n as f32;
+            let rank = ((size as f32 * factor).ceil() as usize).max(1);
+
+            let t0 = Instant::now();
+            let lr = w.low_rank(Rank::Fixed(rank), SvdOptions::default(), &mut rng);
+            let fdt = Instant::now().duration_since(t0);
+
+            let (actual, rdt) = timed(|| a.low_rank_matmul(&lr));
+
+            let mse = expected.mean_squared_error(&actual);
+
+            println!("R: size={size} rank={rank} mse={mse:.6} rdt={rdt:?} factorization={fdt:?}");
+
+            let speedup = dt.as_secs_f32() / rdt.as_secs_f32();
+
+            measurements.push((factor, mse, speedup));
+        }
+
+        {
+            let (label, color, points) = (size.to_string(), color, &measurements);
+
+            cc.draw_series(LineSeries::new(
+                points
+                    .iter()
+                    .cloned()
+                    .map(|(factor, _, speedup)| (factor, speedup)),
+                &color,
+            ))?
+            .label(label)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+
+            cc.draw_series(PointSeries::of_element(
+                points.iter().cloned(),
+                8,
+                ShapeStyle::from(&color).filled(),
+                &|(factor, mse, speedup), size, style| {
+                    EmptyElement::at((factor, speedup))
+                        + Circle::new((0, 0), size as f32 * mse.log10().abs(), style)
+                        + Text::new(format!("{mse:.1e}"), (10, 0), ("sans-serif", 10))
+                },
+            ))?;
+        }
+    }
+
+    // Draw dashed line for 'baseline'
+    cc.draw_series(DashedLineSeries::new(
+        [(0., 1.), (1., 1.)],
+        5,
+        5,
+        BLUE.into(),
+    ))?;
+
+    cc.configure_mesh()
+        .disable_mesh()
+        .x_desc("rank / size")
+        .y_desc("speedup")
+        .draw()?;
+    cc.configure_series_labels()
+        .position(SeriesLabelPosition::UpperRight)
+        .draw()?;
+
+    root.present()?;
+
+    Ok(())
+}
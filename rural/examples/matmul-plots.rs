@@ -3,24 +3,6 @@ use rural::matrix::Matrix;
 use std::error::Error;
 use std::time::{Duration, Instant};
 
-fn mean_squared_error(actual: &Matrix, predicted: &Matrix) -> f32 {
-    assert_eq!(actual.shape(), predicted.shape());
-
-    let mut mse = 0.;
-
-    let (m, n) = actual.shape();
-
-    for i in 0..m {
-        for j in 0..n {
-            let error = actual[(i, j)] - predicted[(i, j)];
-            mse += error * error;
-        }
-    }
-
-    let num_elements = m * n;
-    mse / (num_elements as f32)
-}
-
 fn timed<R>(f: impl Fn() -> R) -> (R, Duration) {
     const I: u64 = 16;
 
@@ -75,7 +57,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 
             let (actual, rdt) = timed(|| a.rand_matmul(&b, factor));
 
-            let mse = mean_squared_error(&expected, &actual);
+            let mse = expected.mean_squared_error(&actual);
 
             println!("R: size={size} factor={factor:.2} mse={mse:.6} rdt={rdt:?}");
 
@@ -0,0 +1,128 @@
+use plotters::prelude::*;
+use rural::matrix::{Matrix, PreparedMatrix};
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+fn timed<R>(f: impl Fn() -> R) -> (R, Duration) {
+    const I: u64 = 64;
+
+    let mut i = 0;
+    let mut dt = 0;
+
+    loop {
+        let t0 = Instant::now();
+        let ret = f();
+
+        dt += Instant::now().duration_since(t0).as_nanos();
+
+        if i >= I {
+            return (ret, Duration::from_nanos((dt / I as u128) as u64));
+        }
+
+        i += 1;
+    }
+}
+
+// Inference-style workload: a small batch of inputs against a large, static weight matrix
+fn main() -> Result<(), Box<dyn Error>> {
+    let root = SVGBackend::new("target/plots-matmul-prepared.svg", (800, 800)).into_drawing_area();
+
+    let mut cc = ChartBuilder::on(&root)
+        .margin(5)
+        .set_all_label_area_size(50)
+        .caption("rand-matmul (prepared weights)", ("sans-serif", 20))
+        .build_cartesian_2d(0f32..1., 0f32..20.)?;
+
+    let (batch_size, input_size, output_size) = (8, 512, 512);
+
+    let input = Matrix::random(batch_size, input_size, 0);
+    let weights = Matrix::random(input_size, output_size, 1);
+
+    let (_, dt) = timed(|| input.matmul(&weights));
+    println!("M: dt={dt:?}");
+
+    // NOTE: `PreparedMatrix::new` takes ownership, so it is timed once, without the clone
+    let owned = weights.clone();
+    let t0 = Instant::now();
+    let prepared = PreparedMatrix::new(owned);
+    let prepare_dt = Instant::now().duration_since(t0);
+    println!("P: prepare_dt={prepare_dt:?}");
+
+    let mut measurements = vec![];
+
+    let n = 100;
+    for factor in [5, 10, 25, 50, 75, 90, 100] {
+        let factor = factor as f32 / n as f32;
+
+        let (_, rdt) = timed(|| input.rand_matmul(&weights, factor));
+        let (_, pdt) = timed(|| input.rand_matmul_prepared(&prepared, factor));
+
+        let (plan, plan_dt) = timed(|| prepared.fixed_plan(factor));
+        let (_, fdt) = timed(|| input.rand_matmul_fixed(&plan));
+
+        let speedup = |t: Duration| dt.as_secs_f32() / t.as_secs_f32();
+
+        // number of calls after which preparing the weights pays for itself (if ever)
+        let amortized = if rdt > pdt {
+            format!(
+                "{:.1} calls",
+                prepare_dt.as_secs_f32() / (rdt - pdt).as_secs_f32()
+            )
+        } else {
+            "n/a".to_string()
+        };
+
+        println!(
+            "R: factor={factor:.2} rdt={rdt:?} pdt={pdt:?} fdt={fdt:?} plan_dt={plan_dt:?} \
+             speedup={:.2}/{:.2}/{:.2} break-even={amortized}",
+            speedup(rdt),
+            speedup(pdt),
+            speedup(fdt),
+        );
+
+        measurements.push((factor, speedup(rdt), speedup(pdt), speedup(fdt)));
+    }
+
+    for (label, color, select) in [
+        ("rand_matmul", RGBColor(200, 0, 0), 0),
+        ("rand_matmul_prepared", RGBColor(0, 200, 0), 1),
+        ("rand_matmul_fixed", RGBColor(0, 0, 200), 2),
+    ] {
+        let points: Vec<_> = measurements
+            .iter()
+            .map(|&(factor, r, p, f)| (factor, [r, p, f][select]))
+            .collect();
+
+        cc.draw_series(LineSeries::new(points.iter().cloned(), &color))?
+            .label(label)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+
+        cc.draw_series(PointSeries::of_element(
+            points,
+            4,
+            ShapeStyle::from(&color).filled(),
+            &|coord, size, style| EmptyElement::at(coord) + Circle::new((0, 0), size, style),
+        ))?;
+    }
+
+    // Draw dashed line for 'baseline'
+    cc.draw_series(DashedLineSeries::new(
+        [(0., 1.), (1., 1.)],
+        5,
+        5,
+        BLUE.into(),
+    ))?;
+
+    cc.configure_mesh()
+        .disable_mesh()
+        .x_desc("factor")
+        .y_desc("speedup")
+        .draw()?;
+    cc.configure_series_labels()
+        .position(SeriesLabelPosition::UpperRight)
+        .draw()?;
+
+    root.present()?;
+
+    Ok(())
+}
@@ -1,7 +1,10 @@
 use plotters::prelude::*;
 use rand_core::SeedableRng as _;
 use rand_distr::Distribution as _;
-use rural::conv::{conv1d, perforation};
+use rural::conv::perforation::{
+    self, MaskFamily, PerforationConfig, PerforationPlan, Reconstruction,
+};
+use rural::conv::{conv1d_auto, Method};
 use std::error::Error;
 use std::time::{Duration, Instant};
 
@@ -39,14 +42,83 @@ fn timed<R>(f: impl Fn() -> R) -> (R, Duration) {
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let root = SVGBackend::new("target/plots-conv.svg", (800, 800)).into_drawing_area();
+    let root = SVGBackend::new("target/plots-conv.svg", (1600, 1000)).into_drawing_area();
+
+    let configs = [
+        MaskFamily::PseudoRandom,
+        MaskFamily::Grid,
+        MaskFamily::Bernoulli,
+        MaskFamily::LowDiscrepancy,
+        MaskFamily::Importance,
+    ]
+    .map(|family| PerforationConfig {
+        family,
+        ..Default::default()
+    });
+
+    compare_reconstructions();
+
+    for (config, area) in configs.iter().zip(root.split_evenly((2, 3))) {
+        plot(config, &area)?;
+    }
 
+    root.present()?;
+
+    Ok(())
+}
+
+// MSE and time of every reconstruction mode, on a smooth ('audio') signal
+fn compare_reconstructions() {
+    let input: Box<[_]> = (0..1024)
+        .map(|t| (t as f32 * 0.03).sin() + 0.5 * (t as f32 * 0.11).sin())
+        .collect();
+
+    for kernel_size in [4, 16, 64] {
+        let kernel: Box<[_]> = (0..kernel_size).map(|_| 1. / kernel_size as f32).collect();
+        let expected = conv1d_auto(&input, &kernel);
+
+        for reconstruction in [
+            Reconstruction::Nearest,
+            Reconstruction::Linear,
+            Reconstruction::Cubic,
+            Reconstruction::Zero,
+        ] {
+            let config = PerforationConfig {
+                reconstruction,
+                ..Default::default()
+            };
+            let plan = PerforationPlan::new(&config, expected.len());
+            let (actual, rdt) = timed(|| {
+                let mut output = vec![0.; plan.output_size()];
+                plan.execute(&input, &kernel, &mut output);
+                output
+            });
+            let mse = mean_squared_error(&expected, &actual);
+
+            println!("Q: kernel_size={kernel_size} reconstruction={reconstruction:?} mse={mse:.8} rdt={rdt:?}");
+        }
+    }
+}
+
+fn plot<DB: DrawingBackend>(
+    config: &PerforationConfig,
+    root: &DrawingArea<DB, plotters::coord::Shift>,
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
     const MAX_X: f32 = 1400.;
 
-    let mut cc = ChartBuilder::on(&root)
+    let mut cc = ChartBuilder::on(root)
         .margin(5)
         .set_all_label_area_size(50)
-        .caption("Perforated `conv1d` (⍺ = 1.5)", ("sans-serif", 20))
+        .caption(
+            format!(
+                "Perforated `conv1d` ({:?}, ⍺ = {}) vs. the best exact method",
+                config.family, config.alpha
+            ),
+            ("sans-serif", 14),
+        )
         .build_cartesian_2d(0f32..MAX_X, 0f32..2.)?;
 
     let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
@@ -81,17 +153,28 @@ fn main() -> Result<(), Box<dyn Error>> {
             // TODO(toms): use random values for kernel
             let kernel: Box<[_]> = (0..kernel_size).map(|_| dist.sample(&mut rng)).collect();
 
-            let (expected, dt) = timed(|| conv1d(&input, &kernel));
-            println!("M: size={input_size} dt={dt:?}");
+            // the baseline is the fastest exact method (direct or FFT)
+            let method = Method::select(input_size, kernel_size);
+            let (expected, dt) = timed(|| conv1d_auto(&input, &kernel));
+            println!("M: size={input_size} method={method:?} dt={dt:?}");
 
             {
-                let (actual, rdt) = timed(|| perforation::conv1d(&input, &kernel));
+                // the plan is built once, outside of the timing (unless the mask depends on the
+                // input)
+                let (actual, rdt) = match PerforationPlan::try_new(config, expected.len()) {
+                    Ok(plan) => timed(|| {
+                        let mut output = vec![0.; plan.output_size()];
+                        plan.execute(&input, &kernel, &mut output);
+                        output
+                    }),
+                    Err(_) => timed(|| perforation::conv1d_with(&input, &kernel, config)),
+                };
 
                 let mse = mean_squared_error(&expected, &actual);
 
                 let speedup = dt.as_secs_f32() / rdt.as_secs_f32();
 
-                println!("R: input_size={input_size} kernel_size={kernel_size} mse={mse:.6} dt={dt:?} rdt={rdt:?} speedup={speedup:?}");
+                println!("R: family={:?} input_size={input_size} kernel_size={kernel_size} mse={mse:.6} dt={dt:?} rdt={rdt:?} speedup={speedup:?}", config.family);
 
                 measurements.push((input_size as f32, kernel_size as f32, mse, speedup));
             }
@@ -142,7 +225,5 @@ fn main() -> Result<(), Box<dyn Error>> {
         .position(SeriesLabelPosition::UpperLeft)
         .draw()?;
 
-    root.present()?;
-
     Ok(())
 }
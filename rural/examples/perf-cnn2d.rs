@@ -0,0 +1,133 @@
+use plotters::prelude::*;
+use rand_core::SeedableRng as _;
+use rural::conv::perforation::{MaskFamily, PerforationConfig, Reconstruction};
+use rural::conv::{Conv2d, Conv2dOptions, Padding};
+use rural::matrix::Matrix;
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+fn timed<R>(f: impl Fn() -> R) -> (R, Duration) {
+    const I: u32 = 20;
+
+    let t0 = Instant::now();
+    for _ in 1..I {
+        f();
+    }
+    let ret = f();
+
+    (ret, Instant::now().duration_since(t0) / I)
+}
+
+// a spectrogram-like input: a few harmonics drifting over time, plus noise
+fn spectrogram(rows: usize, cols: usize, rng: &mut rand_xoshiro::Xoshiro256PlusPlus) -> Matrix {
+    let noise = Matrix::normal(rows, cols, 0., 0.05, rng);
+
+    Matrix::from_fn(rows, cols, |t, f| {
+        let pitch = 10. + 4. * (t as f32 * 0.02).sin();
+        let harmonics: f32 = (1..6)
+            .map(|h| {
+                let d = f as f32 - h as f32 * pitch;
+                (-d * d / 8.).exp() / h as f32
+            })
+            .sum();
+        harmonics + noise[(t, f)]
+    })
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let root = SVGBackend::new("target/plots-conv2d.svg", (800, 800)).into_drawing_area();
+    root.fill(&WHITE)?;
+
+    let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+    let (in_channels, out_channels) = (2, 8);
+    let input: Vec<_> = (0..in_channels)
+        .map(|_| spectrogram(256, 128, &mut rng))
+        .collect();
+
+    let mut measurements = vec![];
+
+    for kernel_size in [3, 5, 9] {
+        let weights = Matrix::normal(
+            kernel_size * kernel_size * in_channels,
+            out_channels,
+            0.,
+            1. / kernel_size as f32,
+            &mut rng,
+        );
+        let options = Conv2dOptions {
+            padding: (Padding::Causal, Padding::Same),
+            ..Default::default()
+        };
+        let conv = Conv2d::new(
+            in_channels,
+            (kernel_size, kernel_size),
+            weights,
+            &[0.; 8],
+            options,
+        );
+
+        let (expected, dt) = timed(|| conv.forward(&input));
+        println!("M: kernel_size={kernel_size} dt={dt:?}");
+
+        for (reconstruction, color) in [
+            (Reconstruction::Nearest, RED),
+            (Reconstruction::Linear, BLUE),
+            (Reconstruction::Cubic, GREEN),
+        ] {
+            for alpha in [1.5, 2., 4.] {
+                let config = PerforationConfig {
+                    alpha,
+                    family: MaskFamily::Grid,
+                    reconstruction,
+                    ..Default::default()
+                };
+                let (actual, rdt) = timed(|| conv.perforated_with(&input, &config));
+
+                let mse = expected
+                    .iter()
+                    .zip(&actual)
+                    .map(|(e, a)| e.mean_squared_error(a))
+                    .sum::<f32>()
+                    / out_channels as f32;
+                let speedup = dt.as_secs_f32() / rdt.as_secs_f32();
+
+                println!("R: kernel_size={kernel_size} reconstruction={reconstruction:?} alpha={alpha} mse={mse:.6} rdt={rdt:?} speedup={speedup:.2}");
+
+                measurements.push((kernel_size, alpha, mse, speedup, color));
+            }
+        }
+    }
+
+    let mut cc = ChartBuilder::on(&root)
+        .margin(5)
+        .set_all_label_area_size(50)
+        .caption(
+            "Perforated `Conv2d` (grid mask): nearest (red), bilinear (blue), bicubic (green)",
+            ("sans-serif", 16),
+        )
+        .build_cartesian_2d((1e-5f32..1f32).log_scale(), 0f32..6.)?;
+
+    cc.configure_mesh()
+        .disable_mesh()
+        .x_desc("mse")
+        .y_desc("speedup")
+        .draw()?;
+
+    cc.draw_series(
+        measurements
+            .iter()
+            .map(|&(kernel_size, alpha, mse, speedup, color)| {
+                EmptyElement::at((mse, speedup))
+                    + Circle::new((0, 0), 4, color.filled())
+                    + Text::new(
+                        format!("k={kernel_size} ⍺={alpha}"),
+                        (6, -4),
+                        ("sans-serif", 10),
+                    )
+            }),
+    )?;
+
+    root.present()?;
+
+    Ok(())
+}
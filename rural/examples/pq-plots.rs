@@ -0,0 +1,139 @@
+use plotters::prelude::*;
+use rural::matrix::{Maddness, Matrix, PqOptions};
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+fn timed<R>(f: impl Fn() -> R) -> (R, Duration) {
+    const I: u64 = 16;
+
+    let mut i = 0;
+    let mut dt = 0;
+
+    loop {
+        let t0 = Instant::now();
+        let ret = f();
+
+        dt += Instant::now().duration_since(t0).as_nanos();
+
+        if i >= I {
+            return (ret, Duration::from_nanos((dt / I as u128) as u64));
+        }
+
+        i += 1;
+    }
+}
+
+// activations with low-dimensional structure (plus noise), like the outputs of a previous layer;
+// this is where prototypes pay off (on white noise, they carry little information per subspace)
+fn activations(rows: usize, size: usize, seed: u64) -> Matrix {
+    let latent = Matrix::random(rows, 4, seed);
+    let basis = Matrix::random(4, size, 1000);
+
+    &latent.matmul(&basis) + &(Matrix::random(rows, size, seed + 1) * 0.1)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let root = SVGBackend::new("target/plots-pq.svg", (800, 800)).into_drawing_area();
+
+    let (batch_size, input_size, output_size) = (64, 512, 128);
+
+    let training = activations(4096, input_size, 0);
+    let input = activations(batch_size, input_size, 2);
+    let weights = Matrix::random(input_size, output_size, 3);
+
+    let (expected, dt) = timed(|| input.matmul(&weights));
+    println!("M: dt={dt:?}");
+
+    let mut maddness = vec![];
+    let mut sampling = vec![];
+
+    for codebooks in [4, 8, 16, 32, 64] {
+        let options = PqOptions {
+            codebooks,
+            ..PqOptions::default()
+        };
+
+        let t0 = Instant::now();
+        let trained = Maddness::train(&training, &weights, options);
+        let tdt = Instant::now().duration_since(t0);
+
+        let (actual, pdt) = timed(|| input.maddness_matmul(&trained));
+        let mse = expected.mean_squared_error(&actual);
+
+        // smallest `rand_matmul` factor that matches the error
+        let (factor, rdt) = (1..=100)
+            .map(|factor| factor as f32 / 100.)
+            .find(|&factor| {
+                expected.mean_squared_error(&input.rand_matmul(&weights, factor)) <= mse
+            })
+            .map(|factor| (factor, timed(|| input.rand_matmul(&weights, factor)).1))
+            .unwrap_or((1., dt));
+
+        let speedup = |t: Duration| dt.as_secs_f32() / t.as_secs_f32();
+
+        println!(
+            "P: codebooks={codebooks} mse={mse:.6} pdt={pdt:?} training={tdt:?} speedup={:.2} \
+             | rand_matmul: factor={factor:.2} rdt={rdt:?} speedup={:.2}",
+            speedup(pdt),
+            speedup(rdt),
+        );
+
+        maddness.push((codebooks as f32, mse, speedup(pdt)));
+        sampling.push((codebooks as f32, mse, speedup(rdt)));
+    }
+
+    let max_speedup = maddness
+        .iter()
+        .chain(&sampling)
+        .fold(1f32, |s, &(_, _, speedup)| s.max(speedup));
+
+    let mut cc = ChartBuilder::on(&root)
+        .margin(5)
+        .set_all_label_area_size(50)
+        .caption("maddness vs. rand-matmul (same error)", ("sans-serif", 20))
+        .build_cartesian_2d(0f32..70., 0f32..1.1 * max_speedup)?;
+
+    for (label, color, points) in [
+        ("maddness", RGBColor(200, 0, 0), &maddness),
+        ("rand_matmul", RGBColor(0, 0, 200), &sampling),
+    ] {
+        cc.draw_series(LineSeries::new(
+            points.iter().map(|&(x, _, speedup)| (x, speedup)),
+            &color,
+        ))?
+        .label(label)
+        .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+
+        cc.draw_series(PointSeries::of_element(
+            points.iter().cloned(),
+            4,
+            ShapeStyle::from(&color).filled(),
+            &|(x, mse, speedup), size, style| {
+                EmptyElement::at((x, speedup))
+                    + Circle::new((0, 0), size, style)
+                    + Text::new(format!("{mse:.1e}"), (10, 0), ("sans-serif", 10))
+            },
+        ))?;
+    }
+
+    // Draw dashed line for 'baseline'
+    cc.draw_series(DashedLineSeries::new(
+        [(0., 1.), (70., 1.)],
+        5,
+        5,
+        BLUE.into(),
+    ))?;
+
+    cc.configure_mesh()
+        .disable_mesh()
+        .x_desc("codebooks")
+        .y_desc("speedup")
+        .draw()?;
+    cc.configure_series_labels()
+        .position(SeriesLabelPosition::UpperRight)
+        .draw()?;
+
+    root.present()?;
+
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use plotters::prelude::*;
+use rural::matrix::{Granularity, Matrix, Observer, QuantizedMatrix, Scheme};
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+fn timed<R>(f: impl Fn() -> R) -> (R, Duration) {
+    const I: u64 = 16;
+
+    let mut i = 0;
+    let mut dt = 0;
+
+    loop {
+        let t0 = Instant::now();
+        let ret = f();
+
+        dt += Instant::now().duration_since(t0).as_nanos();
+
+        if i >= I {
+            return (ret, Duration::from_nanos((dt / I as u128) as u64));
+        }
+
+        i += 1;
+    }
+}
+
+// Quantized matmul next to `rand_matmul`: the weights are quantized once (per column), the inputs
+// on every call (per row, or per tensor with parameters calibrated on sample inputs)
+fn main() -> Result<(), Box<dyn Error>> {
+    let root = SVGBackend::new("target/plots-quant.svg", (800, 800)).into_drawing_area();
+
+    let mut cc = ChartBuilder::on(&root)
+        .margin(5)
+        .set_all_label_area_size(50)
+        .caption("int8 matmul", ("sans-serif", 20))
+        .build_cartesian_2d(4f32..11., 0f32..5.)?;
+
+    let mut measurements = [vec![], vec![], vec![]];
+
+    for size in [32, 64, 128, 256, 512, 1024] {
+        let batch_size = 8;
+
+        let a = Matrix::random(batch_size, size, 0);
+        let w = Matrix::random(size, size, 1);
+
+        let (expected, dt) = timed(|| a.matmul(&w));
+        println!("M: size={size} dt={dt:?}");
+
+        let qw = w.quantize(Scheme::Symmetric, Granularity::PerColumn);
+
+        let mut observer = Observer::new();
+        for seed in 2..10 {
+            observer.observe(&Matrix::random(batch_size, size, seed));
+        }
+        let params = observer.params(Scheme::Asymmetric);
+
+        let (dynamic, qdt) = timed(|| {
+            a.quantize(Scheme::Symmetric, Granularity::PerRow)
+                .matmul(&qw)
+        });
+        let (calibrated, cdt) = timed(|| {
+            QuantizedMatrix::with_params(&a, Granularity::PerTensor, [params]).matmul(&qw)
+        });
+        let (sampled, rdt) = timed(|| a.rand_matmul(&w, 0.5));
+
+        let x = (size as f32).log2();
+        for (i, (label, actual, t)) in [
+            ("int8 (dynamic)", &dynamic, qdt),
+            ("int8 (calibrated)", &calibrated, cdt),
+            ("rand_matmul (0.5)", &sampled, rdt),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let mse = expected.mean_squared_error(actual);
+            let speedup = dt.as_secs_f32() / t.as_secs_f32();
+
+            println!("Q: size={size} {label}: mse={mse:.6} dt={t:?} speedup={speedup:.2}");
+
+            measurements[i].push((x, mse, speedup));
+        }
+    }
+
+    for (label, color, points) in [
+        ("int8 (dynamic)", RGBColor(200, 0, 0), &measurements[0]),
+        ("int8 (calibrated)", RGBColor(0, 200, 0), &measurements[1]),
+        ("rand_matmul (0.5)", RGBColor(0, 0, 200), &measurements[2]),
+    ] {
+        cc.draw_series(LineSeries::new(
+            points.iter().map(|&(x, _, speedup)| (x, speedup)),
+            &color,
+        ))?
+        .label(label)
+        .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+
+        cc.draw_series(PointSeries::of_element(
+            points.iter().cloned(),
+            4,
+            ShapeStyle::from(&color).filled(),
+            &|(x, mse, speedup), size, style| {
+                EmptyElement::at((x, speedup))
+                    + Circle::new((0, 0), size, style)
+                    + Text::new(format!("{mse:.1e}"), (10, 0), ("sans-serif", 10))
+            },
+        ))?;
+    }
+
+    // Draw dashed line for 'baseline'
+    cc.draw_series(DashedLineSeries::new(
+        [(4., 1.), (11., 1.)],
+        5,
+        5,
+        BLUE.into(),
+    ))?;
+
+    cc.configure_mesh()
+        .disable_mesh()
+        .x_desc("log2(size)")
+        .y_desc("speedup")
+        .draw()?;
+    cc.configure_series_labels()
+        .position(SeriesLabelPosition::UpperRight)
+        .draw()?;
+
+    root.present()?;
+
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use plotters::prelude::*;
+use rand_core::SeedableRng as _;
+use rural::matrix::{Matrix, Sampling, Sketch};
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+fn timed<R>(mut f: impl FnMut() -> R) -> (R, Duration) {
+    const I: u64 = 16;
+
+    let mut i = 0;
+    let mut dt = 0;
+
+    loop {
+        let t0 = Instant::now();
+        let ret = f();
+
+        dt += Instant::now().duration_since(t0).as_nanos();
+
+        if i >= I {
+            return (ret, Duration::from_nanos((dt / I as u128) as u64));
+        }
+
+        i += 1;
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Method {
+    Sampling(Sampling),
+    Sketch(Sketch),
+}
+
+// The whole family of randomized methods on one workload: a long inner dimension, which is the one
+// all of them compress
+fn main() -> Result<(), Box<dyn Error>> {
+    let root = SVGBackend::new("target/plots-sketch.svg", (800, 800)).into_drawing_area();
+
+    let mut cc = ChartBuilder::on(&root)
+        .margin(5)
+        .set_all_label_area_size(50)
+        .caption("sampling vs. sketching", ("sans-serif", 20))
+        .build_cartesian_2d(0f32..1., 0f32..10.)?;
+
+    let (m, n, p) = (64, 2048, 64);
+
+    let a = Matrix::random(m, n, 0);
+    let b = Matrix::random(n, p, 1);
+
+    let (expected, dt) = timed(|| a.matmul(&b));
+    println!("M: dt={dt:?}");
+
+    let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+
+    for (method, color) in [
+        (
+            Method::Sampling(Sampling::WithReplacement),
+            RGBColor(0, 0, 0),
+        ),
+        (Method::Sampling(Sampling::TopC), RGBColor(200, 100, 0)),
+        (Method::Sketch(Sketch::Gaussian), RGBColor(200, 0, 0)),
+        (Method::Sketch(Sketch::CountSketch), RGBColor(0, 200, 0)),
+        (Method::Sketch(Sketch::Srht), RGBColor(0, 0, 200)),
+    ] {
+        let mut measurements = vec![];
+
+        for factor in [1, 2, 5, 10, 25, 50] {
+            let factor = factor as f32 / 100.;
+
+            let (actual, rdt) = timed(|| match method {
+                Method::Sampling(sampling) => a.rand_matmul_with(&b, factor, sampling, &mut rng),
+                Method::Sketch(sketch) => a.sketch_matmul(&b, factor, sketch, &mut rng),
+            });
+
+            let mse = expected.mean_squared_error(&actual);
+            let speedup = dt.as_secs_f32() / rdt.as_secs_f32();
+
+            println!(
+                "R: {method:?} factor={factor:.2} mse={mse:.6} rdt={rdt:?} speedup={speedup:.2}"
+            );
+
+            measurements.push((factor, mse, speedup));
+        }
+
+        cc.draw_series(LineSeries::new(
+            measurements
+                .iter()
+                .map(|&(factor, _, speedup)| (factor, speedup)),
+            &color,
+        ))?
+        .label(format!("{method:?}"))
+        .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+
+        cc.draw_series(PointSeries::of_element(
+            measurements,
+            4,
+            ShapeStyle::from(&color).filled(),
+            &|(factor, mse, speedup), size, style| {
+                EmptyElement::at((factor, speedup))
+                    + Circle::new((0, 0), size, style)
+                    + Text::new(format!("{mse:.1e}"), (10, 0), ("sans-serif", 10))
+            },
+        ))?;
+    }
+
+    // Draw dashed line for 'baseline'
+    cc.draw_series(DashedLineSeries::new(
+        [(0., 1.), (1., 1.)],
+        5,
+        5,
+        BLUE.into(),
+    ))?;
+
+    cc.configure_mesh()
+        .disable_mesh()
+        .x_desc("factor")
+        .y_desc("speedup")
+        .draw()?;
+    cc.configure_series_labels()
+        .position(SeriesLabelPosition::UpperRight)
+        .draw()?;
+
+    root.present()?;
+
+    Ok(())
+}
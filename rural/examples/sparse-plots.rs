@@ -0,0 +1,134 @@
+use plotters::prelude::*;
+use rural::math::inner_product;
+use rural::matrix::{CsrMatrix, Matrix, Pruning};
+use std::error::Error;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+fn timed<R>(f: impl Fn() -> R) -> (R, Duration) {
+    const I: u64 = 16;
+
+    let mut i = 0;
+    let mut dt = 0;
+
+    loop {
+        let t0 = Instant::now();
+        let ret = f();
+
+        dt += Instant::now().duration_since(t0).as_nanos();
+
+        if i >= I {
+            return (ret, Duration::from_nanos((dt / I as u128) as u64));
+        }
+
+        i += 1;
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let root = SVGBackend::new("target/plots-sparse.svg", (800, 800)).into_drawing_area();
+
+    let mut cc = ChartBuilder::on(&root)
+        .margin(5)
+        .set_all_label_area_size(50)
+        .caption("sparse matmul", ("sans-serif", 20))
+        .build_cartesian_2d(0f32..1., 0f32..20.)?;
+
+    let (batch_size, size) = (8, 512);
+
+    let input = Matrix::random(batch_size, size, 0);
+    let weights = Matrix::random(size, size, 1);
+
+    let x = Matrix::random(1, size, 2)[0].to_vec();
+
+    let (_, dt) = timed(|| input.matmul(&weights));
+    let (_, vdt) = timed(|| {
+        (0..size)
+            .map(|i| inner_product(&weights[i], black_box(&x), 0.))
+            .map(black_box)
+            .collect::<Vec<_>>()
+    });
+    println!("M: dt={dt:?} gemv_dt={vdt:?}");
+
+    let mut matmul = vec![];
+    let mut gemv = vec![];
+
+    for sparsity in [50, 75, 90, 95, 98, 99] {
+        let sparsity = sparsity as f32 / 100.;
+
+        let csr = CsrMatrix::from_dense(&weights, Pruning::Sparsity(sparsity));
+
+        let (_, sdt) = timed(|| input.matmul_csr(&csr));
+
+        let (_, gdt) = timed(|| {
+            let mut y = vec![0.; size];
+            csr.gemv(black_box(&x), &mut y);
+            y
+        });
+
+        let speedup = dt.as_secs_f32() / sdt.as_secs_f32();
+        let gemv_speedup = vdt.as_secs_f32() / gdt.as_secs_f32();
+
+        println!(
+            "S: sparsity={sparsity:.2} nnz={} sdt={sdt:?} gdt={gdt:?} speedup={speedup:.2}/{gemv_speedup:.2}",
+            csr.nnz()
+        );
+
+        matmul.push((sparsity, speedup));
+        gemv.push((sparsity, gemv_speedup));
+    }
+
+    // N:M structured sparsity (fixed 1 - n/m)
+    let mut structured = vec![];
+    for (n, m) in [(2, 4), (1, 4), (1, 8)] {
+        let csr = CsrMatrix::from_dense(&weights, Pruning::Structured { n, m });
+
+        let (_, sdt) = timed(|| input.matmul_csr(&csr));
+        let speedup = dt.as_secs_f32() / sdt.as_secs_f32();
+
+        println!(
+            "N:M: {n}:{m} sparsity={:.2} sdt={sdt:?} speedup={speedup:.2}",
+            csr.sparsity()
+        );
+
+        structured.push((csr.sparsity(), speedup));
+    }
+
+    for (label, color, points) in [
+        ("matmul (unstructured)", RGBColor(200, 0, 0), &matmul),
+        ("gemv (unstructured)", RGBColor(0, 200, 0), &gemv),
+        ("matmul (N:M)", RGBColor(0, 0, 200), &structured),
+    ] {
+        cc.draw_series(LineSeries::new(points.iter().cloned(), &color))?
+            .label(label)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+
+        cc.draw_series(PointSeries::of_element(
+            points.iter().cloned(),
+            4,
+            ShapeStyle::from(&color).filled(),
+            &|coord, size, style| EmptyElement::at(coord) + Circle::new((0, 0), size, style),
+        ))?;
+    }
+
+    // Draw dashed line for 'baseline'
+    cc.draw_series(DashedLineSeries::new(
+        [(0., 1.), (1., 1.)],
+        5,
+        5,
+        BLUE.into(),
+    ))?;
+
+    cc.configure_mesh()
+        .disable_mesh()
+        .x_desc("sparsity")
+        .y_desc("speedup")
+        .draw()?;
+    cc.configure_series_labels()
+        .position(SeriesLabelPosition::UpperLeft)
+        .draw()?;
+
+    root.present()?;
+
+    Ok(())
+}
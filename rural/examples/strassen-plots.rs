@@ -0,0 +1,141 @@
+use plotters::prelude::*;
+use rural::matrix::{Matrix, Variant};
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+fn timed<R>(f: impl Fn() -> R) -> (R, Duration) {
+    const I: u64 = 4;
+
+    let mut i = 0;
+    let mut dt = 0;
+
+    loop {
+        let t0 = Instant::now();
+        let ret = f();
+
+        dt += Instant::now().duration_since(t0).as_nanos();
+
+        if i >= I {
+            return (ret, Duration::from_nanos((dt / I as u128) as u64));
+        }
+
+        i += 1;
+    }
+}
+
+// `a·b` accumulated in f64, as the reference for the error study
+fn reference(a: &Matrix, b: &Matrix) -> Vec<f64> {
+    let (m, n) = a.shape();
+    let (_n, p) = b.shape();
+
+    let mut result = vec![0f64; m * p];
+    for i in 0..m {
+        for k in 0..n {
+            let a = a[(i, k)] as f64;
+            for j in 0..p {
+                result[i * p + j] += a * b[(k, j)] as f64;
+            }
+        }
+    }
+    result
+}
+
+// max. elementwise error, relative to the largest element of the exact product
+fn relative_error(expected: &[f64], actual: &Matrix) -> f64 {
+    let (m, p) = actual.shape();
+
+    let scale = expected.iter().fold(0f64, |s, x| s.max(x.abs()));
+    let error = (0..m * p).fold(0f64, |e, k| {
+        e.max((expected[k] - actual[(k / p, k % p)] as f64).abs())
+    });
+
+    error / scale
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let root = SVGBackend::new("target/plots-strassen.svg", (800, 800)).into_drawing_area();
+
+    let mut cc = ChartBuilder::on(&root)
+        .margin(5)
+        .set_all_label_area_size(50)
+        .caption("strassen / winograd", ("sans-serif", 20))
+        .build_cartesian_2d(0f32..1100., 0f32..3.)?;
+
+    let mut measurements = vec![];
+
+    for size in [64, 128, 256, 384, 512, 768, 1000, 1024] {
+        let a = Matrix::random(size, size, 0);
+        let b = Matrix::random(size, size, 1);
+
+        let exact = reference(&a, &b);
+
+        let (naive, dt) = timed(|| a.matmul(&b));
+        println!(
+            "M: size={size} dt={dt:?} error={:.2e}",
+            relative_error(&exact, &naive)
+        );
+
+        for cutoff in [32, 64, 128] {
+            for variant in [Variant::Strassen, Variant::Winograd] {
+                let (actual, sdt) = timed(|| a.fast_matmul(&b, cutoff, variant));
+
+                let error = relative_error(&exact, &actual);
+                let speedup = dt.as_secs_f32() / sdt.as_secs_f32();
+
+                println!(
+                    "S: size={size} {variant:?} cutoff={cutoff} dt={sdt:?} \
+                     speedup={speedup:.2} error={error:.2e}"
+                );
+
+                measurements.push((variant, cutoff, size as f32, speedup));
+            }
+        }
+    }
+
+    for (variant, cutoff, color) in [
+        (Variant::Strassen, 32, RGBColor(200, 0, 0)),
+        (Variant::Strassen, 64, RGBColor(200, 100, 0)),
+        (Variant::Strassen, 128, RGBColor(200, 0, 200)),
+        (Variant::Winograd, 32, RGBColor(0, 0, 200)),
+        (Variant::Winograd, 64, RGBColor(0, 100, 200)),
+        (Variant::Winograd, 128, RGBColor(0, 200, 0)),
+    ] {
+        let points: Vec<_> = measurements
+            .iter()
+            .filter(|m| m.0 == variant && m.1 == cutoff)
+            .map(|&(_, _, size, speedup)| (size, speedup))
+            .collect();
+
+        cc.draw_series(LineSeries::new(points.iter().cloned(), &color))?
+            .label(format!("{variant:?} (cutoff={cutoff})"))
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+
+        cc.draw_series(PointSeries::of_element(
+            points,
+            4,
+            ShapeStyle::from(&color).filled(),
+            &|coord, size, style| EmptyElement::at(coord) + Circle::new((0, 0), size, style),
+        ))?;
+    }
+
+    // Draw dashed line for 'baseline'
+    cc.draw_series(DashedLineSeries::new(
+        [(0., 1.), (1100., 1.)],
+        5,
+        5,
+        BLUE.into(),
+    ))?;
+
+    cc.configure_mesh()
+        .disable_mesh()
+        .x_desc("size")
+        .y_desc("speedup")
+        .draw()?;
+    cc.configure_series_labels()
+        .position(SeriesLabelPosition::UpperLeft)
+        .draw()?;
+
+    root.present()?;
+
+    Ok(())
+}
@@ -1,123 +1,173 @@
+use crate::error::{Error, OrPanic, Result};
+use crate::matrix::{Matrix, MatrixView};
+
+pub mod fft;
+mod layer;
+mod layer2d;
+mod norm;
+pub mod perforation;
+mod pool;
+mod streaming;
+
+pub use layer::{Conv1d, Conv1dOptions, Padding, PaddingMode};
+pub use layer2d::{Conv2d, Conv2dOptions};
+pub use norm::BatchNorm;
+pub use pool::{Interpolation, Pool1d, Pool2d, PoolKind, Upsample1d};
+pub use streaming::StreamingConv1d;
+
 pub fn conv1d(input: &[f32], kernel: &[f32]) -> Vec<f32> {
-    let output_size = input.len() - kernel.len() + 1;
+    try_conv1d(input, kernel).or_panic()
+}
 
-    (0..output_size)
+pub fn try_conv1d(input: &[f32], kernel: &[f32]) -> Result<Vec<f32>> {
+    let output_size = output_size(input, kernel)?;
+
+    Ok((0..output_size)
         .map(|i| crate::math::inner_product(&input[i..], kernel, 0.))
-        .collect()
+        .collect())
 }
 
-pub mod perforation {
-    use rand_core::SeedableRng;
-    use rand_distr::Distribution;
-
-    fn find_index_of_nearest_neighbor<T: PartialEq>(
-        arr: &[T],
-        index: usize,
-        value: T,
-    ) -> Option<usize> {
-        let mut left = index;
-        let mut right = index;
-
-        while left > 0 || right < arr.len() - 1 {
-            if left > 0 {
-                if arr[left - 1] == value {
-                    return Some(left - 1);
-                }
-                left -= 1;
-            }
-
-            if right < arr.len() - 1 {
-                if arr[right + 1] == value {
-                    return Some(right + 1);
-                }
-                right += 1;
-            }
-        }
+/// How to compute an exact `conv1d`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    /// `O(N K)` inner products.
+    Direct,
+    /// Overlap-save with [`fft::fft_size`] (see [`fft`]).
+    Fft,
+}
 
-        None
-    }
+impl Method {
+    /// The (estimated) fastest method for an input of `input_len` and a kernel of `kernel_len`.
+    pub fn select(input_len: usize, kernel_len: usize) -> Self {
+        // relative cost of a butterfly vs. a (vectorized) multiply-add, measured on x86_64
+        const FFT_COST: f32 = 8.;
+
+        let Some(output_size) = (input_len + 1).checked_sub(kernel_len) else {
+            return Self::Direct;
+        };
+
+        let n = fft::fft_size(kernel_len).min(input_len.max(1).next_power_of_two());
+        let blocks = output_size.div_ceil(n - kernel_len + 1);
 
-    fn pseudo_mask(length: usize) -> (Box<[bool]>, Box<[(usize, usize)]>) {
-        let alpha = 1.5;
-        let uniform_range = (0., 1.);
-
-        let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
-        let uniform = rand_distr::Uniform::new(uniform_range.0, uniform_range.1);
-
-        let mut sequence = vec![false; length];
-        for a in (0..)
-            .map(|i| {
-                let i = i as f32;
-                let u = uniform.sample(&mut rng);
-                (alpha * (i + u)).ceil() as usize
-            })
-            .take_while(|&a| a < length)
-        {
-            sequence[a] = true;
+        let direct = (output_size * kernel_len) as f32;
+        // one forward and one inverse transform per block
+        let fft = FFT_COST * (blocks * n) as f32 * (n as f32).log2();
+
+        if fft < direct {
+            Self::Fft
+        } else {
+            Self::Direct
         }
+    }
+}
+
+/// `conv1d` with the fastest exact [`Method`] for the sizes involved.
+pub fn conv1d_auto(input: &[f32], kernel: &[f32]) -> Vec<f32> {
+    try_conv1d_auto(input, kernel).or_panic()
+}
+
+pub fn try_conv1d_auto(input: &[f32], kernel: &[f32]) -> Result<Vec<f32>> {
+    match Method::select(input.len(), kernel.len()) {
+        Method::Direct => try_conv1d(input, kernel),
+        Method::Fft => fft::try_conv1d(input, kernel),
+    }
+}
 
-        let neighbors = (0..length)
-            .filter(|&i| !sequence[i])
-            .map(|i| {
-                let neighbor = find_index_of_nearest_neighbor(&sequence, i, true).unwrap();
-                (i, neighbor)
-            })
-            .collect();
+/// `conv1d` with padding and stride (see [`Conv1dOptions`]).
+pub fn conv1d_with(input: &[f32], kernel: &[f32], options: Conv1dOptions) -> Vec<f32> {
+    try_conv1d_with(input, kernel, options).or_panic()
+}
 
-        (sequence.into_boxed_slice(), neighbors)
+pub fn try_conv1d_with(input: &[f32], kernel: &[f32], options: Conv1dOptions) -> Result<Vec<f32>> {
+    if kernel.is_empty() {
+        return Err(Error::EmptyInput);
     }
 
-    pub fn conv1d(input: &[f32], kernel: &[f32]) -> Vec<f32> {
-        let output_size = input.len() - kernel.len() + 1;
+    let weights = Matrix::from_vec((kernel.len(), 1), kernel.to_vec());
+    let conv = Conv1d::try_new(1, kernel.len(), weights, &[0.], options)?;
 
-        let (mask, neighbors) = pseudo_mask(output_size);
+    let input = MatrixView::new(input, (input.len(), 1), (1, 1));
+    let output = conv.try_forward(input)?;
+    Ok((0..output.shape().0).map(|t| output[(t, 0)]).collect())
+}
 
-        debug_assert_eq!(mask.len(), output_size);
+/// The 'valid' 2-D cross-correlation of `input` with `kernel` (see [`Conv2d`]).
+pub fn conv2d<'a, 'b>(
+    input: impl Into<MatrixView<'a>>,
+    kernel: impl Into<MatrixView<'b>>,
+) -> Matrix {
+    try_conv2d(input, kernel).or_panic()
+}
 
-        let mut output: Vec<_> = (0..output_size)
-            .map(|i| {
-                if mask[i] {
-                    crate::math::inner_product(&input[i..], kernel, 0.)
-                } else {
-                    0. // placeholder - fill in with `neighbors`
-                }
-            })
-            .collect();
+pub fn try_conv2d<'a, 'b>(
+    input: impl Into<MatrixView<'a>>,
+    kernel: impl Into<MatrixView<'b>>,
+) -> Result<Matrix> {
+    let kernel = kernel.into();
+    if kernel.shape().0 * kernel.shape().1 == 0 {
+        return Err(Error::EmptyInput);
+    }
 
-        for &(i, j) in neighbors.iter() {
-            output[i] = output[j];
-        }
+    let mut output = Conv2d::from_kernel(kernel).try_forward(&[input.into().to_matrix()])?;
+    Ok(output.remove(0))
+}
 
-        output
+// size of the 'valid' output
+fn output_size(input: &[f32], kernel: &[f32]) -> Result<usize> {
+    if kernel.is_empty() {
+        return Err(Error::EmptyInput);
+    }
+    if kernel.len() > input.len() {
+        return Err(Error::InvalidArgument(format!(
+            "kernel of length {} is longer than the input ({})",
+            kernel.len(),
+            input.len()
+        )));
     }
 
-    #[test]
-    fn baseline() {
-        let input: Box<[_]> = (0..20).map(|i| i as f32 + 1.).collect();
-        let kernel: Box<[_]> = (0..3).map(|_| 1.).collect();
-
-        let result = super::conv1d(&input, &kernel);
-        println!("conv1d: {result:?}");
-        assert_eq!(
-            result.as_slice(),
-            &[
-                6., 9., 12., 15., 18., 21., 24., 27., 30., 33., 36., 39., 42., 45., 48., 51., 54.,
-                57.
-            ]
-        );
-
-        // let mask_size = input.len() - kernel.len() + 1;
-        // let mask = pseudo_mask(mask_size);
-        // println!("mask[{mask_size}]: {mask:?}",);
-
-        let result = conv1d(&input, &kernel);
-        println!("perforation::conv1d: {result:?}");
-        assert_eq!(
-            result.as_slice(),
-            &[
-                9., 9., 9., 15., 18., 21., 21., 27., 30., 30., 39., 39., 42., 45., 48., 48., 54.,
-                57.
-            ]
-        );
+    Ok(input.len() - kernel.len() + 1)
+}
+
+#[test]
+fn invalid_kernel() {
+    let input = [1., 2., 3.];
+
+    assert!(matches!(try_conv1d(&input, &[]), Err(Error::EmptyInput)));
+    assert!(matches!(
+        try_conv1d(&input, &[1.; 4]),
+        Err(Error::InvalidArgument(_))
+    ));
+    assert_eq!(try_conv1d(&input, &[1.; 3]).unwrap(), [6.]);
+    assert!(perforation::try_conv1d(&input, &[1.; 4]).is_err());
+    assert_eq!(perforation::conv1d(&input, &[1.; 3]), [6.]);
+}
+
+#[test]
+fn method() {
+    assert_eq!(Method::select(1024, 3), Method::Direct);
+    assert_eq!(Method::select(1024, 512), Method::Fft);
+    assert_eq!(Method::select(3, 4), Method::Direct);
+
+    let input: Vec<f32> = (0..1024).map(|i| (i as f32 * 0.1).sin()).collect();
+    for kernel_len in [3, 512] {
+        let kernel = vec![1. / kernel_len as f32; kernel_len];
+        let expected = conv1d(&input, &kernel);
+        let actual = conv1d_auto(&input, &kernel);
+        assert!(expected
+            .iter()
+            .zip(&actual)
+            .all(|(e, a)| (e - a).abs() < 1e-4));
     }
+    assert!(try_conv1d_auto(&input, &[]).is_err());
+}
+
+#[test]
+fn conv2d_single_channel() {
+    let input = Matrix::from_fn(4, 5, |i, j| (i * 5 + j) as f32);
+    let kernel = Matrix::new(&[[1., 0.], [0., -1.]]);
+
+    // every output is `x[i][j] - x[i + 1][j + 1]`
+    assert_eq!(conv2d(&input, &kernel), Matrix::fill(3, 4, -6.));
+    assert!(try_conv2d(&input, &Matrix::zeroes(5, 1)).is_err());
+    assert!(try_conv2d(&input, &Matrix::zeroes(0, 1)).is_err());
 }
@@ -0,0 +1,342 @@
+//! FFT-based (exact, up to rounding) `conv1d`, for long kernels.
+//!
+//! The direct method costs `O(N K)`; with an FFT of size `n ≥ K`, the input is processed in blocks
+//! of `n - K + 1` outputs at `O(n log n)` each, i.e. `O(N log n)` in total:
+//! * overlap-save: FFT overlapping input blocks of size `n`, and keep the outputs that don't wrap
+//!   around
+//! * overlap-add: FFT disjoint input blocks (zero-padded to `n`), and add up the overlapping
+//!   (linear) convolutions of the blocks
+//!
+//! The FFT is an in-place iterative radix-2 transform; block sizes are always powers of two.
+
+use crate::error::{Error, OrPanic, Result};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Complex {
+    re: f32,
+    im: f32,
+}
+
+impl Complex {
+    fn mul(self, other: Self) -> Self {
+        Self {
+            re: self.re * other.re - self.im * other.im,
+            im: self.re * other.im + self.im * other.re,
+        }
+    }
+}
+
+// precomputed twiddle factors and bit-reversal permutation of a power-of-two size
+#[derive(Clone, Debug)]
+struct Fft {
+    twiddles: Box<[Complex]>,
+    reversed: Box<[usize]>,
+}
+
+impl Fft {
+    fn new(n: usize) -> Self {
+        debug_assert!(n.is_power_of_two());
+
+        let twiddles = (0..n / 2)
+            .map(|k| {
+                // NOTE: in `f64`, so that large sizes stay accurate
+                let phase = -2. * core::f64::consts::PI * k as f64 / n as f64;
+                Complex {
+                    re: phase.cos() as f32,
+                    im: phase.sin() as f32,
+                }
+            })
+            .collect();
+
+        let bits = n.trailing_zeros();
+        let reversed = (0..n)
+            .map(|i| {
+                if n > 1 {
+                    i.reverse_bits() >> (usize::BITS - bits)
+                } else {
+                    0
+                }
+            })
+            .collect();
+
+        Self { twiddles, reversed }
+    }
+
+    fn len(&self) -> usize {
+        self.reversed.len()
+    }
+
+    // unnormalized; the inverse transform conjugates the twiddles
+    fn transform(&self, x: &mut [Complex], inverse: bool) {
+        let n = self.len();
+        debug_assert_eq!(x.len(), n);
+
+        for (i, &j) in self.reversed.iter().enumerate() {
+            if i < j {
+                x.swap(i, j);
+            }
+        }
+
+        let mut half = 1;
+        while half < n {
+            let step = n / (2 * half);
+            for start in (0..n).step_by(2 * half) {
+                for k in 0..half {
+                    let mut w = self.twiddles[k * step];
+                    if inverse {
+                        w.im = -w.im;
+                    }
+
+                    let a = x[start + k];
+                    let b = x[start + k + half].mul(w);
+                    x[start + k] = Complex {
+                        re: a.re + b.re,
+                        im: a.im + b.im,
+                    };
+                    x[start + k + half] = Complex {
+                        re: a.re - b.re,
+                        im: a.im - b.im,
+                    };
+                }
+            }
+            half *= 2;
+        }
+    }
+}
+
+/// `conv1d` against a fixed kernel, with its spectrum precomputed for one FFT size.
+#[derive(Clone, Debug)]
+pub struct FftConv1d {
+    kernel_len: usize,
+    fft: Fft,
+    // spectrum of the reversed kernel (correlation is convolution with the reversed kernel)
+    spectrum: Box<[Complex]>,
+}
+
+/// The FFT size with the lowest estimated cost per output for a kernel of length `kernel_len`.
+pub fn fft_size(kernel_len: usize) -> usize {
+    let min = kernel_len.max(1).next_power_of_two();
+
+    (0..6)
+        .map(|i| min << i)
+        .min_by(|&a, &b| {
+            let cost = |n: usize| n as f32 * (n as f32).log2() / (n - kernel_len + 1) as f32;
+            cost(a).total_cmp(&cost(b))
+        })
+        .unwrap_or(min)
+}
+
+impl FftConv1d {
+    /// `fft_size` must be a power of two, at least `kernel.len()`.
+    pub fn new(kernel: &[f32], fft_size: usize) -> Self {
+        Self::try_new(kernel, fft_size).or_panic()
+    }
+
+    pub fn try_new(kernel: &[f32], fft_size: usize) -> Result<Self> {
+        if kernel.is_empty() {
+            return Err(Error::EmptyInput);
+        }
+        if !fft_size.is_power_of_two() || fft_size < kernel.len() {
+            return Err(Error::InvalidArgument(format!(
+                "FFT size {fft_size} for a kernel of length {} (must be a power of two, at least \
+                 the kernel length)",
+                kernel.len()
+            )));
+        }
+
+        let fft = Fft::new(fft_size);
+
+        let mut spectrum = vec![Complex::default(); fft_size];
+        for (y, &x) in spectrum.iter_mut().zip(kernel.iter().rev()) {
+            y.re = x;
+        }
+        fft.transform(&mut spectrum, false);
+
+        Ok(Self {
+            kernel_len: kernel.len(),
+            fft,
+            spectrum: spectrum.into(),
+        })
+    }
+
+    pub fn fft_size(&self) -> usize {
+        self.fft.len()
+    }
+
+    // circular convolution of `block` (in place) with the kernel, including the `1 / n`
+    fn convolve_block(&self, block: &mut [Complex]) {
+        self.fft.transform(block, false);
+        for (x, &h) in block.iter_mut().zip(self.spectrum.iter()) {
+            *x = x.mul(h);
+        }
+        self.fft.transform(block, true);
+
+        let scale = 1. / self.fft_size() as f32;
+        for x in block.iter_mut() {
+            x.re *= scale;
+        }
+    }
+
+    /// Same as [`crate::conv::conv1d`], using overlap-save.
+    pub fn overlap_save(&self, input: &[f32]) -> Vec<f32> {
+        self.try_overlap_save(input).or_panic()
+    }
+
+    pub fn try_overlap_save(&self, input: &[f32]) -> Result<Vec<f32>> {
+        let output_size = self.output_size(input)?;
+        let (n, k) = (self.fft_size(), self.kernel_len);
+        let step = n - k + 1;
+
+        let mut output = Vec::with_capacity(output_size);
+        let mut block = vec![Complex::default(); n];
+
+        for start in (0..output_size).step_by(step) {
+            let end = (start + n).min(input.len());
+            for (y, &x) in block.iter_mut().zip(&input[start..end]) {
+                *y = Complex { re: x, im: 0. };
+            }
+            block[end - start..].fill(Complex::default());
+
+            self.convolve_block(&mut block);
+
+            // the first `k - 1` outputs wrap around
+            let count = step.min(output_size - start);
+            output.extend(block[k - 1..k - 1 + count].iter().map(|x| x.re));
+        }
+
+        Ok(output)
+    }
+
+    /// Same as [`crate::conv::conv1d`], using overlap-add.
+    pub fn overlap_add(&self, input: &[f32]) -> Vec<f32> {
+        self.try_overlap_add(input).or_panic()
+    }
+
+    pub fn try_overlap_add(&self, input: &[f32]) -> Result<Vec<f32>> {
+        let output_size = self.output_size(input)?;
+        let (n, k) = (self.fft_size(), self.kernel_len);
+        let step = n - k + 1;
+
+        // the full (linear) convolution, of which the 'valid' part is `[k - 1, input.len())`
+        let mut full = vec![0f32; input.len() + k - 1];
+        let mut block = vec![Complex::default(); n];
+
+        for start in (0..input.len()).step_by(step) {
+            let end = (start + step).min(input.len());
+            for (y, &x) in block.iter_mut().zip(&input[start..end]) {
+                *y = Complex { re: x, im: 0. };
+            }
+            block[end - start..].fill(Complex::default());
+
+            self.convolve_block(&mut block);
+
+            // a block of `step` inputs has `n` outputs
+            for (y, x) in full[start..].iter_mut().zip(block.iter()) {
+                *y += x.re;
+            }
+        }
+
+        full.truncate(input.len());
+        full.drain(..k - 1);
+        debug_assert_eq!(full.len(), output_size);
+
+        Ok(full)
+    }
+
+    fn output_size(&self, input: &[f32]) -> Result<usize> {
+        if self.kernel_len > input.len() {
+            return Err(Error::InvalidArgument(format!(
+                "kernel of length {} is longer than the input ({})",
+                self.kernel_len,
+                input.len()
+            )));
+        }
+
+        Ok(input.len() - self.kernel_len + 1)
+    }
+}
+
+/// Same as [`crate::conv::conv1d`], by overlap-save with [`fft_size`].
+pub fn conv1d(input: &[f32], kernel: &[f32]) -> Vec<f32> {
+    try_conv1d(input, kernel).or_panic()
+}
+
+pub fn try_conv1d(input: &[f32], kernel: &[f32]) -> Result<Vec<f32>> {
+    // NOTE: no need for a larger FFT than the whole input
+    let n = fft_size(kernel.len()).min(input.len().max(1).next_power_of_two());
+    FftConv1d::try_new(kernel, n)?.try_overlap_save(input)
+}
+
+#[test]
+fn fft() {
+    // the DFT of an impulse is flat, and the inverse (scaled by `1 / n`) is the identity
+    let fft = Fft::new(8);
+    let mut x = vec![Complex::default(); 8];
+    x[0].re = 1.;
+    fft.transform(&mut x, false);
+    assert!(x.iter().all(|x| x.re == 1. && x.im == 0.));
+
+    let signal: Vec<Complex> = (0..16)
+        .map(|i| Complex {
+            re: (i as f32).sin(),
+            im: (i as f32 * 0.3).cos(),
+        })
+        .collect();
+    let fft = Fft::new(16);
+    let mut x = signal.clone();
+    fft.transform(&mut x, false);
+
+    // matches the DFT
+    for (k, x) in x.iter().enumerate() {
+        let mut expected = Complex::default();
+        for (i, s) in signal.iter().enumerate() {
+            let phase = -2. * core::f32::consts::PI * (i * k) as f32 / 16.;
+            let w = Complex {
+                re: phase.cos(),
+                im: phase.sin(),
+            };
+            let y = s.mul(w);
+            expected.re += y.re;
+            expected.im += y.im;
+        }
+        assert!((x.re - expected.re).abs() < 1e-4 && (x.im - expected.im).abs() < 1e-4);
+    }
+
+    fft.transform(&mut x, true);
+    for (x, s) in x.iter().zip(&signal) {
+        assert!((x.re / 16. - s.re).abs() < 1e-5 && (x.im / 16. - s.im).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn fft_conv1d() {
+    let input: Vec<f32> = (0..300).map(|i| (i as f32 * 0.37).sin()).collect();
+
+    for kernel_len in [1usize, 2, 7, 64, 100, 300] {
+        let kernel: Vec<f32> = (0..kernel_len).map(|i| (i as f32 * 1.3).cos()).collect();
+        let expected = super::conv1d(&input, &kernel);
+
+        let close = |actual: &[f32]| {
+            actual.len() == expected.len()
+                && actual
+                    .iter()
+                    .zip(&expected)
+                    .all(|(a, e)| (a - e).abs() < 1e-3 * kernel_len as f32)
+        };
+
+        for n in [kernel_len.next_power_of_two(), 128, 512] {
+            if n < kernel_len {
+                continue;
+            }
+            let conv = FftConv1d::new(&kernel, n);
+            assert!(close(&conv.overlap_save(&input)), "save {kernel_len} {n}");
+            assert!(close(&conv.overlap_add(&input)), "add {kernel_len} {n}");
+        }
+        assert!(close(&conv1d(&input, &kernel)));
+    }
+
+    assert!(fft_size(100) >= 128);
+    assert!(FftConv1d::try_new(&[1.; 5], 4).is_err());
+    assert!(FftConv1d::try_new(&[1.; 5], 12).is_err());
+    assert!(try_conv1d(&[1.; 3], &[1.; 4]).is_err());
+}
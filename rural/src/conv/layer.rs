@@ -0,0 +1,742 @@
+//! Multi-channel 1-D convolution layer, with the weight layout of RTNeural's JSON models
+//! (`weights[0]` is `[kernel][in / groups][out]`, `weights[1]` is the bias).
+//!
+//! Like `conv1d` (and Keras' `Conv1D`), this is a cross-correlation:
+//! `y[t][o] = b[o] + Σ_k Σ_c w[k][c][o] x[t·stride + k·dilation - pad][g(o)·in/groups + c]`,
+//! where `x` is padded as selected by [`Padding`] and [`PaddingMode`]. The output sizes follow
+//! TensorFlow (and PyTorch, which pads 'same' the same way); with `R = (kernel - 1) dilation + 1`:
+//! * `Valid`: `floor((time - R) / stride) + 1`, no padding
+//! * `Same`: `ceil(time / stride)`, the padding split evenly (the extra one on the right)
+//! * `Causal`: `ceil(time / stride)`, `R - 1` on the left
+//! * `Full`: `floor((time + R - 2) / stride) + 1`, `R - 1` on both sides
+//!
+//! [`Conv1d::forward_im2col_with`] lowers the layer onto matmuls (im2col), so that any of the
+//! (approximate) `Matrix` multiplications can be used instead.
+//!
+//! NOTE: RTNeural stores the kernel reversed, since its (causal, streaming) state is ordered from
+//! newest to oldest; both compute the same outputs.
+
+use super::perforation::{PerforationConfig, PerforationPlan};
+use crate::error::{Error, OrPanic, Result};
+use crate::matrix::{Matrix, MatrixView};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Padding {
+    #[default]
+    Valid,
+    Same,
+    /// Left-padded, so that every output only depends on current and past inputs.
+    Causal,
+    Full,
+}
+
+/// Values of the padded samples.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PaddingMode {
+    #[default]
+    Zeros,
+    /// Mirrored at the edge samples, without repeating them (`d c b | a b c d | c b a`); the
+    /// padding must be shorter than the input.
+    Reflect,
+    /// Repeats the edge samples (`a a a | a b c d | d d d`).
+    Replicate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Conv1dOptions {
+    /// Spacing between kernel taps.
+    pub dilation: usize,
+    /// Input and output channels are split into `groups` independent groups (depthwise with
+    /// `groups = in_channels`).
+    pub groups: usize,
+    /// Spacing between output steps.
+    pub stride: usize,
+    pub padding: Padding,
+    pub padding_mode: PaddingMode,
+}
+
+impl Default for Conv1dOptions {
+    fn default() -> Self {
+        Self {
+            dilation: 1,
+            groups: 1,
+            stride: 1,
+            padding: Padding::default(),
+            padding_mode: PaddingMode::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Conv1d {
+    in_channels: usize,
+    kernel_size: usize,
+    options: Conv1dOptions,
+    // `(kernel_size * in_channels / groups, out_channels)`, i.e. `[kernel][in / groups][out]`
+    weights: Matrix,
+    bias: Box<[f32]>,
+}
+
+impl Conv1d {
+    /// `weights` of shape `(kernel_size * in_channels / groups, out_channels)`, with rows in
+    /// `[kernel][in / groups]` order.
+    pub fn new(
+        in_channels: usize,
+        kernel_size: usize,
+        weights: Matrix,
+        bias: &[f32],
+        options: Conv1dOptions,
+    ) -> Self {
+        Self::try_new(in_channels, kernel_size, weights, bias, options).or_panic()
+    }
+
+    pub fn try_new(
+        in_channels: usize,
+        kernel_size: usize,
+        weights: Matrix,
+        bias: &[f32],
+        options: Conv1dOptions,
+    ) -> Result<Self> {
+        let Conv1dOptions {
+            dilation,
+            groups,
+            stride,
+            ..
+        } = options;
+        let out_channels = weights.shape().1;
+
+        if kernel_size == 0 || in_channels == 0 || out_channels == 0 {
+            return Err(Error::EmptyInput);
+        }
+        if dilation == 0 || stride == 0 {
+            return Err(Error::InvalidArgument(format!(
+                "dilation {dilation} and stride {stride} (must be positive)"
+            )));
+        }
+        if groups == 0
+            || !in_channels.is_multiple_of(groups)
+            || !out_channels.is_multiple_of(groups)
+        {
+            return Err(Error::InvalidArgument(format!(
+                "{groups} groups for {in_channels} input and {out_channels} output channels"
+            )));
+        }
+        if weights.shape().0 != kernel_size * in_channels / groups {
+            return Err(Error::ShapeMismatch {
+                lhs: (kernel_size * in_channels / groups, out_channels),
+                rhs: weights.shape(),
+            });
+        }
+        if bias.len() != out_channels {
+            return Err(Error::ShapeMismatch {
+                lhs: (1, out_channels),
+                rhs: (1, bias.len()),
+            });
+        }
+
+        Ok(Self {
+            in_channels,
+            kernel_size,
+            options,
+            weights,
+            bias: bias.into(),
+        })
+    }
+
+    /// From the nested `[kernel][in / groups][out]` weights of an RTNeural JSON `conv1d` layer.
+    pub fn from_rtneural<K, C>(weights: &[K], bias: &[f32], options: Conv1dOptions) -> Self
+    where
+        K: AsRef<[C]>,
+        C: AsRef<[f32]>,
+    {
+        Self::try_from_rtneural(weights, bias, options).or_panic()
+    }
+
+    pub fn try_from_rtneural<K, C>(
+        weights: &[K],
+        bias: &[f32],
+        options: Conv1dOptions,
+    ) -> Result<Self>
+    where
+        K: AsRef<[C]>,
+        C: AsRef<[f32]>,
+    {
+        let kernel_size = weights.len();
+        let in_channels = weights.first().map_or(0, |w| w.as_ref().len()) * options.groups;
+
+        let rows: Vec<&[f32]> = weights
+            .iter()
+            .flat_map(|w| w.as_ref().iter().map(AsRef::as_ref))
+            .collect();
+        if rows.len() * options.groups != kernel_size * in_channels {
+            return Err(Error::InvalidArgument(
+                "kernel taps with different numbers of input channels".to_string(),
+            ));
+        }
+
+        Self::try_new(
+            in_channels,
+            kernel_size,
+            Matrix::try_from_rows(&rows)?,
+            bias,
+            options,
+        )
+    }
+
+    /// A single channel `kernel` without bias, i.e. `conv1d`.
+    pub fn from_kernel(kernel: &[f32]) -> Self {
+        let weights = Matrix::from_vec((kernel.len(), 1), kernel.to_vec());
+        Self::new(1, kernel.len(), weights, &[0.], Conv1dOptions::default())
+    }
+
+    pub fn in_channels(&self) -> usize {
+        self.in_channels
+    }
+
+    pub fn out_channels(&self) -> usize {
+        self.weights.shape().1
+    }
+
+    pub fn kernel_size(&self) -> usize {
+        self.kernel_size
+    }
+
+    pub fn options(&self) -> Conv1dOptions {
+        self.options
+    }
+
+    /// `(kernel_size * in_channels / groups, out_channels)`, see [`Conv1d::new`].
+    pub fn weights(&self) -> &Matrix {
+        &self.weights
+    }
+
+    pub fn bias(&self) -> &[f32] {
+        &self.bias
+    }
+
+    /// Number of input steps covered by the kernel.
+    pub fn receptive_field(&self) -> usize {
+        (self.kernel_size - 1) * self.options.dilation + 1
+    }
+
+    /// Number of output steps for an input of length `time`.
+    pub fn output_size(&self, time: usize) -> Result<usize> {
+        Ok(self.layout(time)?.0)
+    }
+
+    // output size and left padding
+    fn layout(&self, time: usize) -> Result<(usize, usize)> {
+        let Conv1dOptions {
+            stride,
+            padding,
+            padding_mode,
+            ..
+        } = self.options;
+
+        layout(time, self.receptive_field(), stride, padding, padding_mode)
+    }
+
+    // `x(k, c)` for output step `t` (see `output_row`)
+    fn taps<'a>(
+        &self,
+        input: MatrixView<'a>,
+        left: usize,
+        t: usize,
+    ) -> impl Fn(usize, usize) -> f32 + 'a {
+        let Conv1dOptions {
+            dilation,
+            stride,
+            padding_mode,
+            ..
+        } = self.options;
+
+        move |k, c| {
+            let i = (t * stride + k * dilation) as isize - left as isize;
+            padded_index(i, input.shape().0, padding_mode).map_or(0., |i| input[(i, c)])
+        }
+    }
+
+    fn check_input(&self, input: MatrixView) -> Result<()> {
+        let (time, channels) = input.shape();
+
+        if channels != self.in_channels {
+            return Err(Error::ShapeMismatch {
+                lhs: (time, self.in_channels),
+                rhs: input.shape(),
+            });
+        }
+
+        Ok(())
+    }
+
+    // one output step, with `x(k, c)` the input of channel `c` at kernel tap `k`
+    pub(super) fn output_row(&self, x: impl Fn(usize, usize) -> f32, row: &mut [f32]) {
+        let groups = self.options.groups;
+        let in_group = self.in_channels / groups;
+        let out_group = self.out_channels() / groups;
+
+        row.copy_from_slice(&self.bias);
+
+        for k in 0..self.kernel_size {
+            for g in 0..groups {
+                let outputs = g * out_group..(g + 1) * out_group;
+                for c in 0..in_group {
+                    let v = x(k, g * in_group + c);
+                    let w = &self.weights[k * in_group + c][outputs.clone()];
+                    for (y, w) in row[outputs.clone()].iter_mut().zip(w) {
+                        *y += v * w;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Convolves `input` of shape `(time, in_channels)`; the output has shape
+    /// `(output_size(time), out_channels)`.
+    pub fn forward<'a>(&self, input: impl Into<MatrixView<'a>>) -> Matrix {
+        self.try_forward(input).or_panic()
+    }
+
+    pub fn try_forward<'a>(&self, input: impl Into<MatrixView<'a>>) -> Result<Matrix> {
+        let input = input.into();
+        self.check_input(input)?;
+        let (output_size, left) = self.layout(input.shape().0)?;
+
+        let mut output = Matrix::zeroes(output_size, self.out_channels());
+        for t in 0..output_size {
+            self.output_row(self.taps(input, left, t), &mut output[t]);
+        }
+
+        Ok(output)
+    }
+
+    /// The im2col lowering of `input`: one row per output step, with the input taps of every
+    /// group in `[group][kernel][in / groups]` order, so that the forward pass is the matmul of
+    /// every group's columns with its weights (see [`Conv1d::forward_im2col`]).
+    pub fn im2col<'a>(&self, input: impl Into<MatrixView<'a>>) -> Matrix {
+        self.try_im2col(input).or_panic()
+    }
+
+    pub fn try_im2col<'a>(&self, input: impl Into<MatrixView<'a>>) -> Result<Matrix> {
+        let input = input.into();
+        self.check_input(input)?;
+        let (output_size, left) = self.layout(input.shape().0)?;
+
+        let in_group = self.in_channels / self.options.groups;
+        let width = self.kernel_size * in_group;
+
+        let mut columns = Matrix::zeroes(output_size, width * self.options.groups);
+        for t in 0..output_size {
+            let x = self.taps(input, left, t);
+            for (i, y) in columns[t].iter_mut().enumerate() {
+                let (g, k, c) = (i / width, i % width / in_group, i % in_group);
+                *y = x(k, g * in_group + c);
+            }
+        }
+
+        Ok(columns)
+    }
+
+    /// Same as [`Conv1d::forward`], as matmuls of the [`Conv1d::im2col`] columns with the weights.
+    pub fn forward_im2col<'a>(&self, input: impl Into<MatrixView<'a>>) -> Matrix {
+        self.try_forward_im2col(input).or_panic()
+    }
+
+    pub fn try_forward_im2col<'a>(&self, input: impl Into<MatrixView<'a>>) -> Result<Matrix> {
+        self.try_forward_im2col_with(input, |x, w| x.matmul(w))
+    }
+
+    /// [`Conv1d::forward_im2col`], with `matmul(columns, weights)` for every group, e.g. an
+    /// approximate matmul.
+    pub fn forward_im2col_with<'a>(
+        &self,
+        input: impl Into<MatrixView<'a>>,
+        matmul: impl FnMut(MatrixView, MatrixView) -> Matrix,
+    ) -> Matrix {
+        self.try_forward_im2col_with(input, matmul).or_panic()
+    }
+
+    pub fn try_forward_im2col_with<'a>(
+        &self,
+        input: impl Into<MatrixView<'a>>,
+        mut matmul: impl FnMut(MatrixView, MatrixView) -> Matrix,
+    ) -> Result<Matrix> {
+        let columns = self.try_im2col(input)?;
+        let output_size = columns.shape().0;
+
+        let groups = self.options.groups;
+        let width = self.kernel_size * self.in_channels / groups;
+        let out_group = self.out_channels() / groups;
+
+        let mut output = Matrix::zeroes(output_size, self.out_channels());
+        for g in 0..groups {
+            let outputs = g * out_group..(g + 1) * out_group;
+            let y = matmul(
+                columns.slice(.., g * width..(g + 1) * width),
+                self.weights.slice(.., outputs.clone()),
+            );
+            if y.shape() != (output_size, out_group) {
+                return Err(Error::ShapeMismatch {
+                    lhs: (output_size, out_group),
+                    rhs: y.shape(),
+                });
+            }
+            output.slice_mut(.., outputs).copy_from(&y);
+        }
+
+        for t in 0..output_size {
+            for (y, b) in output[t].iter_mut().zip(self.bias.iter()) {
+                *y += b;
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Same as [`Conv1d::forward`], but only computes the output steps selected by the default
+    /// perforation mask (see `perforation::conv1d`); the others are copied from their nearest
+    /// computed neighbor.
+    pub fn perforated<'a>(&self, input: impl Into<MatrixView<'a>>) -> Matrix {
+        self.try_perforated(input).or_panic()
+    }
+
+    pub fn try_perforated<'a>(&self, input: impl Into<MatrixView<'a>>) -> Result<Matrix> {
+        self.try_perforated_with(input, &PerforationConfig::default())
+    }
+
+    /// [`Conv1d::perforated`] with the mask and reconstruction of `config`. The importance of a
+    /// step is the energy of its input window, over all channels.
+    pub fn perforated_with<'a>(
+        &self,
+        input: impl Into<MatrixView<'a>>,
+        config: &PerforationConfig,
+    ) -> Matrix {
+        self.try_perforated_with(input, config).or_panic()
+    }
+
+    pub fn try_perforated_with<'a>(
+        &self,
+        input: impl Into<MatrixView<'a>>,
+        config: &PerforationConfig,
+    ) -> Result<Matrix> {
+        let input = input.into();
+        self.check_input(input)?;
+        let (output_size, left) = self.layout(input.shape().0)?;
+
+        let plan = PerforationPlan::with_importance(config, output_size, |t| {
+            let x = self.taps(input, left, t);
+            (0..self.kernel_size)
+                .flat_map(|k| (0..self.in_channels).map(move |c| (k, c)))
+                .map(|(k, c)| x(k, c).powi(2))
+                .sum()
+        })?;
+
+        let out_channels = self.out_channels();
+        let mut output = vec![0.; output_size * out_channels];
+        for (t, row) in output.chunks_exact_mut(out_channels).enumerate() {
+            if plan.mask()[t] {
+                self.output_row(self.taps(input, left, t), row);
+            }
+        }
+        plan.reconstruct(&mut output, out_channels);
+
+        Matrix::try_from_vec((output_size, out_channels), output)
+    }
+}
+
+/// Output size and left padding along an axis of length `time`, for a receptive field `r`.
+pub(super) fn layout(
+    time: usize,
+    r: usize,
+    stride: usize,
+    padding: Padding,
+    padding_mode: PaddingMode,
+) -> Result<(usize, usize)> {
+    if time == 0 {
+        return Err(Error::EmptyInput);
+    }
+
+    let (left, right) = match padding {
+        Padding::Valid => (0, 0),
+        Padding::Same => {
+            let total = ((time.div_ceil(stride) - 1) * stride + r).saturating_sub(time);
+            (total / 2, total - total / 2)
+        }
+        Padding::Causal => (r - 1, 0),
+        Padding::Full => (r - 1, r - 1),
+    };
+
+    let padded = time + left + right;
+    if padded < r {
+        return Err(Error::InvalidArgument(format!(
+            "input of length {time} is shorter than the receptive field ({r})"
+        )));
+    }
+    if padding_mode == PaddingMode::Reflect && left.max(right) >= time {
+        return Err(Error::InvalidArgument(format!(
+            "reflect padding of {} for an input of length {time}",
+            left.max(right)
+        )));
+    }
+
+    Ok(((padded - r) / stride + 1, left))
+}
+
+/// The input index of padded index `i` (relative to the unpadded input of length `len`), or
+/// `None` for a zero.
+pub(super) fn padded_index(i: isize, len: usize, padding_mode: PaddingMode) -> Option<usize> {
+    let len = len as isize;
+    let i = match padding_mode {
+        _ if (0..len).contains(&i) => i,
+        PaddingMode::Zeros => return None,
+        PaddingMode::Reflect if i < 0 => -i,
+        PaddingMode::Reflect => 2 * (len - 1) - i,
+        PaddingMode::Replicate => i.clamp(0, len - 1),
+    };
+
+    Some(i as usize)
+}
+
+#[test]
+fn im2col() {
+    use rand_core::SeedableRng;
+
+    let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+    let input = Matrix::normal(30, 4, 0., 1., &mut rng);
+
+    for options in [
+        Conv1dOptions::default(),
+        Conv1dOptions {
+            dilation: 2,
+            groups: 2,
+            stride: 3,
+            ..Default::default()
+        },
+        Conv1dOptions {
+            groups: 4,
+            padding: Padding::Same,
+            padding_mode: PaddingMode::Reflect,
+            ..Default::default()
+        },
+    ] {
+        let in_group = 4 / options.groups;
+        let weights = Matrix::normal(3 * in_group, 8, 0., 1., &mut rng);
+        let bias: Vec<f32> = (0..8).map(|o| o as f32 * 0.1).collect();
+        let conv = Conv1d::new(4, 3, weights, &bias, options);
+
+        let expected = conv.forward(&input);
+        let columns = conv.im2col(&input);
+        assert_eq!(columns.shape(), (expected.shape().0, 3 * 4));
+
+        // the same, up to rounding, without approximation
+        assert!(expected.max_abs_error(&conv.forward_im2col(&input)) < 1e-5);
+        let exact = conv.forward_im2col_with(&input, |x, w| x.rand_matmul(w, 1.));
+        assert!(expected.max_abs_error(&exact) < 1e-5);
+
+        // and any matmul, e.g. an approximate one
+        let approximate = conv.forward_im2col_with(&input, |x, w| x.rand_matmul(w, 0.5));
+        assert!(expected.relative_error(&approximate) < 1.);
+    }
+
+    let conv = Conv1d::from_kernel(&[1., 2.]);
+    let input = Matrix::new(&[[1.], [2.], [3.]]);
+    assert_eq!(conv.im2col(&input), Matrix::new(&[[1., 2.], [2., 3.]]));
+    assert!(conv
+        .try_forward_im2col_with(&input, |_, _| Matrix::zeroes(1, 1))
+        .is_err());
+}
+
+#[test]
+fn conv1d_layer() {
+    use rand_core::SeedableRng;
+
+    let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+
+    // a single channel is `conv1d`, plus the bias
+    let input = Matrix::random(20, 1, 0);
+    let kernel = Matrix::random(5, 1, 1);
+    let conv = Conv1d::new(1, 5, kernel.clone(), &[0.5], Conv1dOptions::default());
+
+    let x: Vec<f32> = (0..20).map(|t| input[(t, 0)]).collect();
+    let k: Vec<f32> = (0..5).map(|t| kernel[(t, 0)]).collect();
+    let expected: Vec<f32> = super::conv1d(&x, &k).iter().map(|y| y + 0.5).collect();
+    let output = conv.forward(&input);
+    assert_eq!(output.shape(), (16, 1));
+    for (t, y) in expected.iter().enumerate() {
+        assert!((output[(t, 0)] - y).abs() < 1e-5);
+    }
+    let expected: Vec<f32> = super::perforation::conv1d(&x, &k)
+        .iter()
+        .map(|y| y + 0.5)
+        .collect();
+    let output = conv.perforated(&input);
+    for (t, y) in expected.iter().enumerate() {
+        assert!((output[(t, 0)] - y).abs() < 1e-5);
+    }
+
+    // RTNeural's layout, with dilation and groups
+    let (kernel_size, in_channels, out_channels) = (3, 4, 8);
+    for options in [
+        Conv1dOptions::default(),
+        Conv1dOptions {
+            dilation: 2,
+            groups: 2,
+            ..Default::default()
+        },
+        // depthwise, with a channel multiplier of 2
+        Conv1dOptions {
+            dilation: 3,
+            groups: 4,
+            ..Default::default()
+        },
+    ] {
+        let in_group = in_channels / options.groups;
+        let out_group = out_channels / options.groups;
+
+        let w: Vec<Vec<Vec<f32>>> = (0..kernel_size)
+            .map(|k| Matrix::random(in_group, out_channels, k as u64))
+            .map(|w| (0..in_group).map(|c| w[c].to_vec()).collect())
+            .collect();
+        let bias: Vec<f32> = (0..out_channels).map(|o| o as f32).collect();
+        let conv = Conv1d::from_rtneural(&w, &bias, options);
+        assert_eq!(conv.in_channels(), in_channels);
+        assert_eq!(conv.out_channels(), out_channels);
+
+        let input = Matrix::normal(30, in_channels, 0., 1., &mut rng);
+        let output = conv.forward(&input);
+        let receptive_field = (kernel_size - 1) * options.dilation + 1;
+        assert_eq!(output.shape(), (30 - receptive_field + 1, out_channels));
+
+        for t in 0..output.shape().0 {
+            for o in 0..out_channels {
+                let g = o / out_group;
+                let mut y = bias[o];
+                for k in 0..kernel_size {
+                    for c in 0..in_group {
+                        y += w[k][c][o] * input[(t + k * options.dilation, g * in_group + c)];
+                    }
+                }
+                assert!((output[(t, o)] - y).abs() < 1e-4, "{options:?}");
+            }
+        }
+
+        // the perforated layer computes some steps exactly, and copies the others
+        let perforated = conv.perforated(&input);
+        assert_eq!(perforated.shape(), output.shape());
+        let exact = (0..output.shape().0)
+            .filter(|&t| perforated[t] == output[t])
+            .count();
+        assert!(0 < exact && exact < output.shape().0);
+
+        // a full mask is exact
+        let config = PerforationConfig {
+            family: super::perforation::MaskFamily::Custom(vec![true; output.shape().0].into()),
+            ..Default::default()
+        };
+        assert_eq!(conv.perforated_with(&input, &config), output);
+    }
+
+    let conv = Conv1d::new(
+        4,
+        3,
+        Matrix::zeroes(12, 6),
+        &[0.; 6],
+        Conv1dOptions::default(),
+    );
+    assert!(conv.try_forward(&Matrix::zeroes(30, 3)).is_err());
+    assert!(conv.try_forward(&Matrix::zeroes(2, 4)).is_err());
+
+    assert!(Conv1d::try_new(
+        4,
+        3,
+        Matrix::zeroes(6, 6),
+        &[0.; 6],
+        Conv1dOptions::default()
+    )
+    .is_err());
+    assert!(Conv1d::try_new(
+        3,
+        2,
+        Matrix::zeroes(6, 4),
+        &[0.; 4],
+        Conv1dOptions {
+            groups: 2,
+            ..Default::default()
+        }
+    )
+    .is_err());
+}
+
+#[test]
+fn padding() {
+    // `conv1d_with` on `[1, 2, 3, 4, 5]` with a `[1, 10, 100]` kernel
+    let input = [1., 2., 3., 4., 5.];
+    let kernel = [1., 10., 100.];
+    let conv = |padding, padding_mode, stride| {
+        let options = Conv1dOptions {
+            stride,
+            padding,
+            padding_mode,
+            ..Default::default()
+        };
+        super::conv1d_with(&input, &kernel, options)
+    };
+
+    use {Padding::*, PaddingMode::*};
+    assert_eq!(conv(Valid, Zeros, 1), [321., 432., 543.]);
+    assert_eq!(conv(Same, Zeros, 1), [210., 321., 432., 543., 54.]);
+    assert_eq!(conv(Causal, Zeros, 1), [100., 210., 321., 432., 543.]);
+    assert_eq!(
+        conv(Full, Zeros, 1),
+        [100., 210., 321., 432., 543., 54., 5.]
+    );
+    assert_eq!(conv(Same, Reflect, 1), [212., 321., 432., 543., 454.]);
+    assert_eq!(
+        conv(Full, Reflect, 1),
+        [123., 212., 321., 432., 543., 454., 345.]
+    );
+    assert_eq!(conv(Causal, Replicate, 1), [111., 211., 321., 432., 543.]);
+    assert_eq!(conv(Valid, Zeros, 2), [321., 543.]);
+    assert_eq!(conv(Causal, Zeros, 2), [100., 321., 543.]);
+    assert_eq!(conv(Full, Replicate, 3), [111., 432., 555.]);
+
+    // TensorFlow's output sizes, for an even receptive field
+    let conv = Conv1d::new(1, 2, Matrix::ones(2, 1), &[0.], Default::default());
+    for (time, stride, padding, size) in [
+        (10, 1, Valid, 9),
+        (10, 3, Valid, 3),
+        (10, 3, Same, 4),
+        (10, 4, Causal, 3),
+        (10, 4, Full, 3),
+        (1, 2, Same, 1),
+    ] {
+        let options = Conv1dOptions {
+            stride,
+            padding,
+            ..Default::default()
+        };
+        let conv = Conv1d::new(1, 2, conv.weights().clone(), &[0.], options);
+        assert_eq!(conv.output_size(time).unwrap(), size, "{options:?}");
+    }
+
+    // 'same' splits the padding with the extra one on the right: `[1 + 2, ..., 5 + 0]`
+    let options = Conv1dOptions {
+        padding: Same,
+        ..Default::default()
+    };
+    let conv = Conv1d::new(1, 2, Matrix::ones(2, 1), &[0.], options);
+    let x = Matrix::from_vec((5, 1), input.to_vec());
+    assert_eq!(
+        conv.forward(&x),
+        Matrix::new(&[[3.], [5.], [7.], [9.], [5.]])
+    );
+
+    let options = Conv1dOptions {
+        padding: Full,
+        padding_mode: Reflect,
+        ..Default::default()
+    };
+    let conv = Conv1d::new(1, 5, Matrix::ones(5, 1), &[0.], options);
+    assert!(conv.try_forward(&Matrix::zeroes(4, 1)).is_err());
+    assert!(conv.try_forward(&Matrix::zeroes(5, 1)).is_ok());
+}
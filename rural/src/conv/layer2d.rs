@@ -0,0 +1,689 @@
+//! Multi-channel 2-D convolution layer (e.g. over spectrograms), the 2-D counterpart of
+//! [`Conv1d`](super::Conv1d).
+//!
+//! Every channel is a `(rows, cols)` matrix, and the weights are `[row][col][in][out]`:
+//! `y[o][i][j] = b[o] + Σ_ki Σ_kj Σ_c w[ki][kj][c][o] x[c][i·s0 + ki·d0 - p0][j·s1 + kj·d1 - p1]`,
+//! where the padding and output size along each axis follow `Conv1d`.
+//!
+//! NOTE: RTNeural's `conv2d` runs over time (the rows) with a causal padding, i.e.
+//! `padding: (Padding::Causal, _)`.
+
+use super::layer::{layout, padded_index};
+use super::perforation::{MaskFamily, PerforationConfig, PerforationPlan};
+use super::{Padding, PaddingMode};
+use crate::error::{Error, OrPanic, Result};
+use crate::matrix::{Matrix, MatrixView};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Conv2dOptions {
+    /// `(rows, cols)` spacing between kernel taps.
+    pub dilation: (usize, usize),
+    /// `(rows, cols)` spacing between outputs.
+    pub stride: (usize, usize),
+    /// Padding of the rows and of the columns.
+    pub padding: (Padding, Padding),
+    pub padding_mode: PaddingMode,
+}
+
+impl Default for Conv2dOptions {
+    fn default() -> Self {
+        Self {
+            dilation: (1, 1),
+            stride: (1, 1),
+            padding: Default::default(),
+            padding_mode: PaddingMode::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Conv2d {
+    in_channels: usize,
+    kernel_size: (usize, usize),
+    options: Conv2dOptions,
+    // `(rows * cols * in_channels, out_channels)`, i.e. `[row][col][in][out]`
+    weights: Matrix,
+    bias: Box<[f32]>,
+}
+
+impl Conv2d {
+    /// `weights` of shape `(kernel rows * kernel cols * in_channels, out_channels)`, with rows in
+    /// `[row][col][in]` order.
+    pub fn new(
+        in_channels: usize,
+        kernel_size: (usize, usize),
+        weights: Matrix,
+        bias: &[f32],
+        options: Conv2dOptions,
+    ) -> Self {
+        Self::try_new(in_channels, kernel_size, weights, bias, options).or_panic()
+    }
+
+    pub fn try_new(
+        in_channels: usize,
+        kernel_size: (usize, usize),
+        weights: Matrix,
+        bias: &[f32],
+        options: Conv2dOptions,
+    ) -> Result<Self> {
+        let Conv2dOptions {
+            dilation, stride, ..
+        } = options;
+        let out_channels = weights.shape().1;
+        let taps = kernel_size.0 * kernel_size.1;
+
+        if taps == 0 || in_channels == 0 || out_channels == 0 {
+            return Err(Error::EmptyInput);
+        }
+        if dilation.0 == 0 || dilation.1 == 0 || stride.0 == 0 || stride.1 == 0 {
+            return Err(Error::InvalidArgument(format!(
+                "dilation {dilation:?} and stride {stride:?} (must be positive)"
+            )));
+        }
+        if weights.shape().0 != taps * in_channels {
+            return Err(Error::ShapeMismatch {
+                lhs: (taps * in_channels, out_channels),
+                rhs: weights.shape(),
+            });
+        }
+        if bias.len() != out_channels {
+            return Err(Error::ShapeMismatch {
+                lhs: (1, out_channels),
+                rhs: (1, bias.len()),
+            });
+        }
+
+        Ok(Self {
+            in_channels,
+            kernel_size,
+            options,
+            weights,
+            bias: bias.into(),
+        })
+    }
+
+    /// A single channel `kernel` without bias, i.e. `conv2d`.
+    pub fn from_kernel<'a>(kernel: impl Into<MatrixView<'a>>) -> Self {
+        let kernel = kernel.into();
+        let (m, n) = kernel.shape();
+        let weights = Matrix::from_fn(m * n, 1, |k, _| kernel[(k / n, k % n)]);
+        Self::new(1, (m, n), weights, &[0.], Conv2dOptions::default())
+    }
+
+    pub fn in_channels(&self) -> usize {
+        self.in_channels
+    }
+
+    pub fn out_channels(&self) -> usize {
+        self.weights.shape().1
+    }
+
+    pub fn kernel_size(&self) -> (usize, usize) {
+        self.kernel_size
+    }
+
+    pub fn options(&self) -> Conv2dOptions {
+        self.options
+    }
+
+    /// `(kernel rows * kernel cols * in_channels, out_channels)`, see [`Conv2d::new`].
+    pub fn weights(&self) -> &Matrix {
+        &self.weights
+    }
+
+    pub fn bias(&self) -> &[f32] {
+        &self.bias
+    }
+
+    /// Number of input rows and columns covered by the kernel.
+    pub fn receptive_field(&self) -> (usize, usize) {
+        let ((m, n), (d0, d1)) = (self.kernel_size, self.options.dilation);
+        ((m - 1) * d0 + 1, (n - 1) * d1 + 1)
+    }
+
+    /// Output shape for inputs of shape `(rows, cols)`.
+    pub fn output_size(&self, shape: (usize, usize)) -> Result<(usize, usize)> {
+        let ((rows, _), (cols, _)) = self.layout(shape)?;
+        Ok((rows, cols))
+    }
+
+    // output size and leading padding of both axes
+    fn layout(&self, (m, n): (usize, usize)) -> Result<((usize, usize), (usize, usize))> {
+        let Conv2dOptions {
+            stride,
+            padding,
+            padding_mode,
+            ..
+        } = self.options;
+        let r = self.receptive_field();
+
+        Ok((
+            layout(m, r.0, stride.0, padding.0, padding_mode)?,
+            layout(n, r.1, stride.1, padding.1, padding_mode)?,
+        ))
+    }
+
+    fn check_input(&self, input: &[Matrix]) -> Result<(usize, usize)> {
+        let shape = input.first().map_or((0, 0), Matrix::shape);
+
+        if input.len() != self.in_channels {
+            return Err(Error::ShapeMismatch {
+                lhs: (self.in_channels, 1),
+                rhs: (input.len(), 1),
+            });
+        }
+        if let Some(x) = input.iter().find(|x| x.shape() != shape) {
+            return Err(Error::ShapeMismatch {
+                lhs: shape,
+                rhs: x.shape(),
+            });
+        }
+
+        Ok(shape)
+    }
+
+    // all output channels at `(i, j)`, into `outputs[o][index]`
+    fn output_at(
+        &self,
+        input: &[Matrix],
+        ((top, left), (i, j)): ((usize, usize), (usize, usize)),
+        outputs: &mut [Vec<f32>],
+        index: usize,
+    ) {
+        let (m, n) = input[0].shape();
+        let (kernel, dilation, stride) =
+            (self.kernel_size, self.options.dilation, self.options.stride);
+        let padding_mode = self.options.padding_mode;
+
+        for (o, output) in outputs.iter_mut().enumerate() {
+            output[index] = self.bias[o];
+        }
+
+        for ki in 0..kernel.0 {
+            let row = (i * stride.0 + ki * dilation.0) as isize - top as isize;
+            let Some(row) = padded_index(row, m, padding_mode) else {
+                continue;
+            };
+
+            for kj in 0..kernel.1 {
+                let col = (j * stride.1 + kj * dilation.1) as isize - left as isize;
+                let Some(col) = padded_index(col, n, padding_mode) else {
+                    continue;
+                };
+
+                for (c, x) in input.iter().enumerate() {
+                    let v = x[(row, col)];
+                    let w = &self.weights[(ki * kernel.1 + kj) * self.in_channels + c];
+                    for (output, w) in outputs.iter_mut().zip(w) {
+                        output[index] += v * w;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Convolves `in_channels` inputs of the same shape into `out_channels` outputs of shape
+    /// `output_size(shape)`.
+    pub fn forward(&self, input: &[Matrix]) -> Vec<Matrix> {
+        self.try_forward(input).or_panic()
+    }
+
+    pub fn try_forward(&self, input: &[Matrix]) -> Result<Vec<Matrix>> {
+        let shape = self.check_input(input)?;
+        let ((rows, top), (cols, left)) = self.layout(shape)?;
+
+        let mut outputs = vec![vec![0.; rows * cols]; self.out_channels()];
+        for i in 0..rows {
+            for j in 0..cols {
+                self.output_at(input, ((top, left), (i, j)), &mut outputs, i * cols + j);
+            }
+        }
+
+        outputs
+            .into_iter()
+            .map(|output| Matrix::try_from_vec((rows, cols), output))
+            .collect()
+    }
+
+    /// The im2col lowering of `input`: one row per output (in row-major order), with the input
+    /// taps in `[row][col][in]` order, so that the forward pass is the matmul of the columns with
+    /// the weights (see [`Conv2d::forward_im2col`]).
+    pub fn im2col(&self, input: &[Matrix]) -> Matrix {
+        self.try_im2col(input).or_panic()
+    }
+
+    pub fn try_im2col(&self, input: &[Matrix]) -> Result<Matrix> {
+        let (m, n) = self.check_input(input)?;
+        let ((rows, top), (cols, left)) = self.layout((m, n))?;
+        let Conv2dOptions {
+            dilation,
+            stride,
+            padding_mode,
+            ..
+        } = self.options;
+        let kernel = self.kernel_size;
+
+        let mut columns = Matrix::zeroes(rows * cols, kernel.0 * kernel.1 * self.in_channels);
+        for (i, j) in (0..rows).flat_map(|i| (0..cols).map(move |j| (i, j))) {
+            let y = &mut columns[i * cols + j];
+            for (ki, kj) in (0..kernel.0).flat_map(|ki| (0..kernel.1).map(move |kj| (ki, kj))) {
+                let row = (i * stride.0 + ki * dilation.0) as isize - top as isize;
+                let col = (j * stride.1 + kj * dilation.1) as isize - left as isize;
+                let (Some(row), Some(col)) = (
+                    padded_index(row, m, padding_mode),
+                    padded_index(col, n, padding_mode),
+                ) else {
+                    continue;
+                };
+
+                let k = (ki * kernel.1 + kj) * self.in_channels;
+                for (c, x) in input.iter().enumerate() {
+                    y[k + c] = x[(row, col)];
+                }
+            }
+        }
+
+        Ok(columns)
+    }
+
+    /// Same as [`Conv2d::forward`], as the matmul of the [`Conv2d::im2col`] columns with the
+    /// weights.
+    pub fn forward_im2col(&self, input: &[Matrix]) -> Vec<Matrix> {
+        self.try_forward_im2col(input).or_panic()
+    }
+
+    pub fn try_forward_im2col(&self, input: &[Matrix]) -> Result<Vec<Matrix>> {
+        self.try_forward_im2col_with(input, |x, w| x.matmul(w))
+    }
+
+    /// [`Conv2d::forward_im2col`], with `matmul(columns, weights)`, e.g. an approximate matmul.
+    pub fn forward_im2col_with(
+        &self,
+        input: &[Matrix],
+        matmul: impl FnOnce(MatrixView, MatrixView) -> Matrix,
+    ) -> Vec<Matrix> {
+        self.try_forward_im2col_with(input, matmul).or_panic()
+    }
+
+    pub fn try_forward_im2col_with(
+        &self,
+        input: &[Matrix],
+        matmul: impl FnOnce(MatrixView, MatrixView) -> Matrix,
+    ) -> Result<Vec<Matrix>> {
+        let (rows, cols) = self.output_size(self.check_input(input)?)?;
+        let columns = self.try_im2col(input)?;
+
+        let y = matmul(columns.view(), self.weights.view());
+        if y.shape() != (rows * cols, self.out_channels()) {
+            return Err(Error::ShapeMismatch {
+                lhs: (rows * cols, self.out_channels()),
+                rhs: y.shape(),
+            });
+        }
+
+        // `(rows * cols, out_channels)` to `out_channels` matrices of `(rows, cols)`
+        Ok((0..self.out_channels())
+            .map(|o| Matrix::from_fn(rows, cols, |i, j| y[(i * cols + j, o)] + self.bias[o]))
+            .collect())
+    }
+
+    /// Same as [`Conv2d::forward`], but only computes the outputs selected by the default
+    /// perforation mask (see [`Conv2d::perforated_with`]).
+    pub fn perforated(&self, input: &[Matrix]) -> Vec<Matrix> {
+        self.try_perforated(input).or_panic()
+    }
+
+    pub fn try_perforated(&self, input: &[Matrix]) -> Result<Vec<Matrix>> {
+        self.try_perforated_with(input, &PerforationConfig::default())
+    }
+
+    /// [`Conv2d::forward`] at the outputs of a 2-D perforation mask: the product of a mask of
+    /// the rows and one of the columns, each from `config` with `sqrt(alpha)` (so that one
+    /// output in `alpha` is computed), the columns with the next seed. A
+    /// [`MaskFamily::Custom`] mask is any 2-D mask instead, of the `(rows, cols)` outputs in
+    /// row-major order. Importance masks are 1-D only.
+    ///
+    /// The others are reconstructed along the columns of every row with a computed output, and
+    /// then along the rows, so `Linear` is bilinear and `Cubic` bicubic interpolation.
+    pub fn perforated_with(&self, input: &[Matrix], config: &PerforationConfig) -> Vec<Matrix> {
+        self.try_perforated_with(input, config).or_panic()
+    }
+
+    pub fn try_perforated_with(
+        &self,
+        input: &[Matrix],
+        config: &PerforationConfig,
+    ) -> Result<Vec<Matrix>> {
+        let shape = self.check_input(input)?;
+        let ((rows, top), (cols, left)) = self.layout(shape)?;
+
+        match &config.family {
+            MaskFamily::Importance => {
+                return Err(Error::Unsupported(
+                    "importance masks for 2-D perforation".to_string(),
+                ))
+            }
+            MaskFamily::Custom(mask) => {
+                return self.perforated_custom(input, config, (top, left), (rows, cols), mask)
+            }
+            _ => {}
+        }
+
+        let axis = PerforationConfig {
+            alpha: config.alpha.sqrt(),
+            ..config.clone()
+        };
+        let row_plan = PerforationPlan::try_new(&axis, rows)?;
+        let col_plan = PerforationPlan::try_new(
+            &PerforationConfig {
+                seed: config.seed.wrapping_add(1),
+                ..axis
+            },
+            cols,
+        )?;
+
+        let mut outputs = vec![vec![0.; rows * cols]; self.out_channels()];
+        for i in (0..rows).filter(|&i| row_plan.mask()[i]) {
+            for j in (0..cols).filter(|&j| col_plan.mask()[j]) {
+                self.output_at(input, ((top, left), (i, j)), &mut outputs, i * cols + j);
+            }
+        }
+
+        outputs
+            .into_iter()
+            .map(|mut output| {
+                for i in (0..rows).filter(|&i| row_plan.mask()[i]) {
+                    col_plan.reconstruct(&mut output[i * cols..(i + 1) * cols], 1);
+                }
+                row_plan.reconstruct(&mut output, cols);
+
+                Matrix::try_from_vec((rows, cols), output)
+            })
+            .collect()
+    }
+
+    // `try_perforated_with` for an arbitrary (row-major) mask of the outputs
+    fn perforated_custom(
+        &self,
+        input: &[Matrix],
+        config: &PerforationConfig,
+        padding: (usize, usize),
+        (rows, cols): (usize, usize),
+        mask: &[bool],
+    ) -> Result<Vec<Matrix>> {
+        if mask.len() != rows * cols {
+            return Err(Error::InvalidArgument(format!(
+                "perforation mask of length {} for ({rows}, {cols}) outputs",
+                mask.len()
+            )));
+        }
+
+        // every output needs a computed neighbor
+        let mut mask = mask.to_vec();
+        if !mask.is_empty() && !mask.contains(&true) {
+            mask[0] = true;
+        }
+
+        let plan = |mask: &[bool], size| {
+            PerforationPlan::try_new(
+                &PerforationConfig {
+                    family: MaskFamily::Custom(mask.into()),
+                    reconstruction: config.reconstruction,
+                    ..Default::default()
+                },
+                size,
+            )
+        };
+
+        // the rows with a computed output, and the plans of their columns
+        let row_plan = plan(
+            &(0..rows)
+                .map(|i| mask[i * cols..(i + 1) * cols].contains(&true))
+                .collect::<Vec<_>>(),
+            rows,
+        )?;
+        let col_plans = (0..rows)
+            .map(|i| {
+                row_plan.mask()[i]
+                    .then(|| plan(&mask[i * cols..(i + 1) * cols], cols))
+                    .transpose()
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut outputs = vec![vec![0.; rows * cols]; self.out_channels()];
+        for (k, _) in mask.iter().enumerate().filter(|(_, &computed)| computed) {
+            self.output_at(input, (padding, (k / cols, k % cols)), &mut outputs, k);
+        }
+
+        outputs
+            .into_iter()
+            .map(|mut output| {
+                for (i, col_plan) in col_plans.iter().enumerate() {
+                    if let Some(col_plan) = col_plan {
+                        col_plan.reconstruct(&mut output[i * cols..(i + 1) * cols], 1);
+                    }
+                }
+                row_plan.reconstruct(&mut output, cols);
+
+                Matrix::try_from_vec((rows, cols), output)
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn conv2d_layer() {
+    use rand_core::SeedableRng;
+
+    let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+
+    let input: Vec<_> = (0..3)
+        .map(|_| Matrix::normal(11, 9, 0., 1., &mut rng))
+        .collect();
+    let weights = Matrix::normal(2 * 3 * 3, 4, 0., 1., &mut rng);
+    let bias = [0.1, 0.2, 0.3, 0.4];
+
+    for options in [
+        Conv2dOptions::default(),
+        Conv2dOptions {
+            dilation: (2, 1),
+            stride: (1, 2),
+            ..Default::default()
+        },
+        Conv2dOptions {
+            padding: (Padding::Causal, Padding::Same),
+            padding_mode: PaddingMode::Reflect,
+            ..Default::default()
+        },
+        Conv2dOptions {
+            stride: (3, 1),
+            padding: (Padding::Full, Padding::Valid),
+            ..Default::default()
+        },
+    ] {
+        let conv = Conv2d::new(3, (2, 3), weights.clone(), &bias, options);
+        let output = conv.forward(&input);
+        let ((rows, top), (cols, left)) = conv.layout((11, 9)).unwrap();
+        assert_eq!(output.len(), 4);
+        assert!(output.iter().all(|y| y.shape() == (rows, cols)));
+
+        // the definition, on the explicitly padded input
+        let x = |c: usize, i: isize, j: isize| {
+            let mode = options.padding_mode;
+            match (padded_index(i, 11, mode), padded_index(j, 9, mode)) {
+                (Some(i), Some(j)) => input[c][(i, j)],
+                _ => 0.,
+            }
+        };
+        for (o, y) in output.iter().enumerate() {
+            for (i, j) in (0..rows).flat_map(|i| (0..cols).map(move |j| (i, j))) {
+                let mut expected = bias[o];
+                for (ki, kj, c) in (0..2)
+                    .flat_map(|ki| (0..3).flat_map(move |kj| (0..3).map(move |c| (ki, kj, c))))
+                {
+                    let row = (i * options.stride.0 + ki * options.dilation.0) as isize;
+                    let col = (j * options.stride.1 + kj * options.dilation.1) as isize;
+                    expected += weights[((ki * 3 + kj) * 3 + c, o)]
+                        * x(c, row - top as isize, col - left as isize);
+                }
+                assert!((y[(i, j)] - expected).abs() < 1e-4);
+            }
+        }
+    }
+
+    // single channel
+    let kernel = Matrix::new(&[[1., 2.], [3., 4.]]);
+    let x = Matrix::new(&[[1., 2., 3.], [4., 5., 6.]]);
+    let y = Conv2d::from_kernel(&kernel).forward(&[x]);
+    assert_eq!(y[0], Matrix::new(&[[37., 47.]]));
+
+    let conv = Conv2d::new(3, (2, 3), weights.clone(), &bias, Default::default());
+    assert!(conv.try_forward(&input[..2]).is_err());
+    assert!(conv
+        .try_forward(&[input[0].clone(), input[1].clone(), Matrix::zeroes(11, 8)])
+        .is_err());
+    assert!(conv.try_forward(&vec![Matrix::zeroes(1, 9); 3]).is_err());
+    assert!(Conv2d::try_new(3, (2, 2), weights.clone(), &bias, Default::default()).is_err());
+}
+
+#[test]
+fn im2col() {
+    use rand_core::SeedableRng;
+
+    let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+    let input: Vec<_> = (0..2)
+        .map(|_| Matrix::normal(12, 10, 0., 1., &mut rng))
+        .collect();
+    let weights = Matrix::normal(3 * 2 * 2, 5, 0., 1., &mut rng);
+    let bias = [0.1, 0.2, 0.3, 0.4, 0.5];
+
+    for options in [
+        Conv2dOptions::default(),
+        Conv2dOptions {
+            dilation: (1, 2),
+            stride: (2, 1),
+            padding: (Padding::Causal, Padding::Same),
+            padding_mode: PaddingMode::Replicate,
+        },
+    ] {
+        let conv = Conv2d::new(2, (3, 2), weights.clone(), &bias, options);
+        let expected = conv.forward(&input);
+
+        // the same, up to rounding, without approximation
+        let actual = conv.forward_im2col(&input);
+        assert_eq!(actual.len(), expected.len());
+        for (e, a) in expected.iter().zip(&actual) {
+            assert!(e.max_abs_error(a) < 1e-5);
+        }
+
+        let (rows, cols) = conv.output_size((12, 10)).unwrap();
+        assert_eq!(conv.im2col(&input).shape(), (rows * cols, 3 * 2 * 2));
+    }
+
+    let conv = Conv2d::new(2, (3, 2), weights, &bias, Default::default());
+    assert!(conv
+        .try_forward_im2col_with(&input, |_, _| Matrix::zeroes(1, 1))
+        .is_err());
+}
+
+#[test]
+fn perforated_conv2d() {
+    use super::perforation::Reconstruction;
+
+    // a plane, convolved, is a plane: bilinear interpolation is exact between computed outputs
+    let plane = Matrix::from_fn(40, 30, |i, j| i as f32 + 2. * j as f32);
+    let conv = Conv2d::from_kernel(&Matrix::fill(3, 3, 1. / 9.));
+    let exact = conv.forward(std::slice::from_ref(&plane));
+
+    let config = PerforationConfig {
+        alpha: 4.,
+        reconstruction: Reconstruction::Linear,
+        ..Default::default()
+    };
+    let bilinear = conv.perforated_with(std::slice::from_ref(&plane), &config);
+    let (rows, cols) = exact[0].shape();
+    let computed = (0..rows)
+        .flat_map(|i| (0..cols).map(move |j| (i, j)))
+        .filter(|&(i, j)| bilinear[0][(i, j)] == exact[0][(i, j)])
+        .count();
+    assert!(computed > 0);
+
+    // interior, away from the extrapolated edges
+    let interior = |y: &Matrix| y.slice(4..rows - 4, 4..cols - 4).to_matrix();
+    assert!(interior(&exact[0]).max_abs_error(&interior(&bilinear[0])) < 1e-3);
+
+    // `Nearest` copies computed outputs, roughly one in `alpha` of which are computed
+    let nearest = conv.perforated_with(
+        std::slice::from_ref(&plane),
+        &PerforationConfig {
+            alpha: 4.,
+            ..Default::default()
+        },
+    );
+    let values = |y: &Matrix| -> Vec<f32> { (0..rows).flat_map(|i| y[i].to_vec()).collect() };
+    let exact_values = values(&exact[0]);
+    assert!(values(&nearest[0]).iter().all(|y| exact_values.contains(y)));
+    let distinct = {
+        let mut values = values(&nearest[0]);
+        values.sort_by(f32::total_cmp);
+        values.dedup();
+        values.len()
+    };
+    assert!((distinct as f32) < 0.5 * (rows * cols) as f32);
+
+    // without perforation, exact
+    let config = PerforationConfig {
+        alpha: 1.,
+        family: MaskFamily::Grid,
+        ..Default::default()
+    };
+    assert_eq!(
+        conv.perforated_with(std::slice::from_ref(&plane), &config),
+        exact
+    );
+    assert!(conv
+        .try_perforated_with(
+            std::slice::from_ref(&plane),
+            &PerforationConfig {
+                family: MaskFamily::Importance,
+                ..Default::default()
+            }
+        )
+        .is_err());
+
+    // an arbitrary 2-D mask: a checkerboard, whose skipped outputs lie between computed ones
+    let checkerboard: Box<[bool]> = (0..rows * cols)
+        .map(|k| (k / cols + k % cols) % 2 == 0)
+        .collect();
+    let config = PerforationConfig {
+        family: MaskFamily::Custom(checkerboard.clone()),
+        reconstruction: Reconstruction::Linear,
+        ..Default::default()
+    };
+    let actual = conv.perforated_with(std::slice::from_ref(&plane), &config);
+    for (k, &computed) in checkerboard.iter().enumerate() {
+        let (i, j) = (k / cols, k % cols);
+        if computed {
+            assert_eq!(actual[0][(i, j)], exact[0][(i, j)]);
+        }
+    }
+    assert!(interior(&exact[0]).max_abs_error(&interior(&actual[0])) < 1e-3);
+
+    // a single computed output, copied everywhere by `Nearest`
+    let mut single = vec![false; rows * cols];
+    single[cols + 2] = true;
+    let config = PerforationConfig {
+        family: MaskFamily::Custom(single.into()),
+        ..Default::default()
+    };
+    let actual = conv.perforated_with(std::slice::from_ref(&plane), &config);
+    assert_eq!(actual[0], Matrix::fill(rows, cols, exact[0][(1, 2)]));
+
+    let config = PerforationConfig {
+        family: MaskFamily::Custom(checkerboard[1..].into()),
+        ..Default::default()
+    };
+    assert!(conv
+        .try_perforated_with(std::slice::from_ref(&plane), &config)
+        .is_err());
+}
@@ -0,0 +1,309 @@
+//! Batch normalization in inference mode, with the parameters of RTNeural's JSON `batchnorm`
+//! (and `batchnorm2d`) layers: `y = gamma (x - mean) / sqrt(variance + epsilon) + beta`.
+//!
+//! Since this is an affine map of every channel, it can be folded into the weights and bias of a
+//! preceding convolution ([`Conv1d::fold_batch_norm`]).
+
+use super::{Conv1d, Conv2d};
+use crate::error::{Error, OrPanic, Result};
+use crate::matrix::{Matrix, MatrixView};
+
+#[derive(Clone, Debug)]
+pub struct BatchNorm {
+    gamma: Box<[f32]>,
+    beta: Box<[f32]>,
+    mean: Box<[f32]>,
+    variance: Box<[f32]>,
+    epsilon: f32,
+    // `y = scale x + offset`
+    scale: Box<[f32]>,
+    offset: Box<[f32]>,
+}
+
+impl BatchNorm {
+    pub fn new(gamma: &[f32], beta: &[f32], mean: &[f32], variance: &[f32], epsilon: f32) -> Self {
+        Self::try_new(gamma, beta, mean, variance, epsilon).or_panic()
+    }
+
+    pub fn try_new(
+        gamma: &[f32],
+        beta: &[f32],
+        mean: &[f32],
+        variance: &[f32],
+        epsilon: f32,
+    ) -> Result<Self> {
+        let channels = mean.len();
+
+        if channels == 0 {
+            return Err(Error::EmptyInput);
+        }
+        if let Some(other) = [gamma, beta, variance].iter().find(|p| p.len() != channels) {
+            return Err(Error::ShapeMismatch {
+                lhs: (1, channels),
+                rhs: (1, other.len()),
+            });
+        }
+        if epsilon.is_nan()
+            || epsilon < 0.
+            || variance.iter().any(|&v| v.is_nan() || v + epsilon <= 0.)
+        {
+            return Err(Error::InvalidArgument(format!(
+                "variance + epsilon ({epsilon}) must be positive"
+            )));
+        }
+
+        let scale: Box<[f32]> = (0..channels)
+            .map(|c| gamma[c] / (variance[c] + epsilon).sqrt())
+            .collect();
+        let offset = (0..channels)
+            .map(|c| beta[c] - mean[c] * scale[c])
+            .collect();
+
+        Ok(Self {
+            gamma: gamma.into(),
+            beta: beta.into(),
+            mean: mean.into(),
+            variance: variance.into(),
+            epsilon,
+            scale,
+            offset,
+        })
+    }
+
+    /// From the `weights` of an RTNeural JSON `batchnorm` layer: `[gamma, beta, running_mean,
+    /// running_var]`, or `[running_mean, running_var]` without the affine parameters.
+    pub fn from_rtneural<W: AsRef<[f32]>>(weights: &[W], epsilon: f32) -> Self {
+        Self::try_from_rtneural(weights, epsilon).or_panic()
+    }
+
+    pub fn try_from_rtneural<W: AsRef<[f32]>>(weights: &[W], epsilon: f32) -> Result<Self> {
+        match weights {
+            [gamma, beta, mean, variance] => Self::try_new(
+                gamma.as_ref(),
+                beta.as_ref(),
+                mean.as_ref(),
+                variance.as_ref(),
+                epsilon,
+            ),
+            [mean, variance] => {
+                let channels = mean.as_ref().len();
+                Self::try_new(
+                    &vec![1.; channels],
+                    &vec![0.; channels],
+                    mean.as_ref(),
+                    variance.as_ref(),
+                    epsilon,
+                )
+            }
+            _ => Err(Error::InvalidArgument(format!(
+                "{} batch norm weights (must be 2 or 4)",
+                weights.len()
+            ))),
+        }
+    }
+
+    pub fn channels(&self) -> usize {
+        self.mean.len()
+    }
+
+    pub fn gamma(&self) -> &[f32] {
+        &self.gamma
+    }
+
+    pub fn beta(&self) -> &[f32] {
+        &self.beta
+    }
+
+    pub fn mean(&self) -> &[f32] {
+        &self.mean
+    }
+
+    pub fn variance(&self) -> &[f32] {
+        &self.variance
+    }
+
+    pub fn epsilon(&self) -> f32 {
+        self.epsilon
+    }
+
+    /// The per-channel affine map: `y = scale x + offset`.
+    pub fn scale(&self) -> &[f32] {
+        &self.scale
+    }
+
+    pub fn offset(&self) -> &[f32] {
+        &self.offset
+    }
+
+    /// Normalizes every channel of `input` of shape `(time, channels)`.
+    pub fn forward<'a>(&self, input: impl Into<MatrixView<'a>>) -> Matrix {
+        self.try_forward(input).or_panic()
+    }
+
+    pub fn try_forward<'a>(&self, input: impl Into<MatrixView<'a>>) -> Result<Matrix> {
+        let input = input.into();
+        let (time, channels) = input.shape();
+
+        if channels != self.channels() {
+            return Err(Error::ShapeMismatch {
+                lhs: (time, self.channels()),
+                rhs: input.shape(),
+            });
+        }
+
+        Ok(Matrix::from_fn(time, channels, |t, c| {
+            self.scale[c] * input[(t, c)] + self.offset[c]
+        }))
+    }
+
+    /// Normalizes every channel of `input`, one `(rows, cols)` matrix per channel.
+    pub fn forward_2d(&self, input: &[Matrix]) -> Vec<Matrix> {
+        self.try_forward_2d(input).or_panic()
+    }
+
+    pub fn try_forward_2d(&self, input: &[Matrix]) -> Result<Vec<Matrix>> {
+        if input.len() != self.channels() {
+            return Err(Error::ShapeMismatch {
+                lhs: (self.channels(), 1),
+                rhs: (input.len(), 1),
+            });
+        }
+
+        Ok(input
+            .iter()
+            .enumerate()
+            .map(|(c, x)| {
+                let (m, n) = x.shape();
+                Matrix::from_fn(m, n, |i, j| self.scale[c] * x[(i, j)] + self.offset[c])
+            })
+            .collect())
+    }
+
+    // `(weights, bias)` of a layer followed by this batch norm
+    fn fold(&self, weights: &Matrix, bias: &[f32]) -> Result<(Matrix, Box<[f32]>)> {
+        let out_channels = weights.shape().1;
+        if out_channels != self.channels() {
+            return Err(Error::ShapeMismatch {
+                lhs: (1, out_channels),
+                rhs: (1, self.channels()),
+            });
+        }
+
+        let (m, n) = weights.shape();
+        let weights = Matrix::from_fn(m, n, |k, o| weights[(k, o)] * self.scale[o]);
+        let bias = (0..n)
+            .map(|o| bias[o] * self.scale[o] + self.offset[o])
+            .collect();
+
+        Ok((weights, bias))
+    }
+}
+
+impl Conv1d {
+    /// The convolution followed by `batch_norm` (over its output channels), as one convolution.
+    pub fn fold_batch_norm(&self, batch_norm: &BatchNorm) -> Conv1d {
+        self.try_fold_batch_norm(batch_norm).or_panic()
+    }
+
+    pub fn try_fold_batch_norm(&self, batch_norm: &BatchNorm) -> Result<Conv1d> {
+        let (weights, bias) = batch_norm.fold(self.weights(), self.bias())?;
+        Conv1d::try_new(
+            self.in_channels(),
+            self.kernel_size(),
+            weights,
+            &bias,
+            self.options(),
+        )
+    }
+}
+
+impl Conv2d {
+    /// The convolution followed by `batch_norm` (over its output channels), as one convolution.
+    pub fn fold_batch_norm(&self, batch_norm: &BatchNorm) -> Conv2d {
+        self.try_fold_batch_norm(batch_norm).or_panic()
+    }
+
+    pub fn try_fold_batch_norm(&self, batch_norm: &BatchNorm) -> Result<Conv2d> {
+        let (weights, bias) = batch_norm.fold(self.weights(), self.bias())?;
+        Conv2d::try_new(
+            self.in_channels(),
+            self.kernel_size(),
+            weights,
+            &bias,
+            self.options(),
+        )
+    }
+}
+
+#[test]
+fn batch_norm() {
+    let bn = BatchNorm::new(&[2., 1.], &[0.5, 0.], &[1., -1.], &[3., 0.], 1.);
+    let input = Matrix::new(&[[1., -1.], [3., 1.]]);
+
+    // `2 (x - 1) / 2 + 0.5` and `x + 1`
+    assert_eq!(bn.forward(&input), Matrix::new(&[[0.5, 0.], [2.5, 2.]]));
+    assert_eq!(
+        bn.forward_2d(&[Matrix::new(&[[1., 3.]]), Matrix::new(&[[-1., 1.]])]),
+        [Matrix::new(&[[0.5, 2.5]]), Matrix::new(&[[0., 2.]])]
+    );
+
+    // RTNeural weights
+    let affine = BatchNorm::from_rtneural(&[[2., 1.], [0.5, 0.], [1., -1.], [3., 0.]], 1.);
+    assert_eq!(affine.forward(&input), bn.forward(&input));
+    let plain = BatchNorm::from_rtneural(&[[1., -1.], [3., 0.]], 1.);
+    assert_eq!(plain.forward(&input), Matrix::new(&[[0., 0.], [1., 2.]]));
+    assert!(BatchNorm::try_from_rtneural(&[[1., -1.]], 1.).is_err());
+
+    assert!(bn.try_forward(&Matrix::zeroes(2, 3)).is_err());
+    assert!(BatchNorm::try_new(&[1.], &[0.], &[0.], &[0.], 0.).is_err());
+    assert!(BatchNorm::try_new(&[1.], &[0., 1.], &[0.], &[1.], 0.).is_err());
+}
+
+#[test]
+fn fold_batch_norm() {
+    use super::{Conv1dOptions, Conv2dOptions, Padding};
+    use rand_core::SeedableRng;
+
+    let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+    let bn = BatchNorm::new(
+        &[1.5, 0.5, -1.],
+        &[0.1, -0.2, 0.3],
+        &[0.2, 0.4, -0.6],
+        &[1., 2., 0.5],
+        1e-3,
+    );
+
+    let conv = Conv1d::new(
+        2,
+        3,
+        Matrix::normal(3 * 2, 3, 0., 1., &mut rng),
+        &[0.1, 0.2, 0.3],
+        Conv1dOptions {
+            padding: Padding::Causal,
+            ..Default::default()
+        },
+    );
+    let input = Matrix::normal(20, 2, 0., 1., &mut rng);
+    let expected = bn.forward(&conv.forward(&input));
+    let folded = conv.fold_batch_norm(&bn);
+    assert!(expected.max_abs_error(&folded.forward(&input)) < 1e-5);
+
+    let conv = Conv2d::new(
+        2,
+        (2, 2),
+        Matrix::normal(2 * 2 * 2, 3, 0., 1., &mut rng),
+        &[0.1, 0.2, 0.3],
+        Conv2dOptions::default(),
+    );
+    let input: Vec<_> = (0..2)
+        .map(|_| Matrix::normal(6, 5, 0., 1., &mut rng))
+        .collect();
+    let expected = bn.forward_2d(&conv.forward(&input));
+    let actual = conv.fold_batch_norm(&bn).forward(&input);
+    for (e, a) in expected.iter().zip(&actual) {
+        assert!(e.max_abs_error(a) < 1e-5);
+    }
+
+    let conv = Conv1d::from_kernel(&[1., 2.]);
+    assert!(conv.try_fold_batch_norm(&bn).is_err());
+}
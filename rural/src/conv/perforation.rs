@@ -0,0 +1,666 @@
+//! Perforated convolution (Figurnov et al., "PerforatedCNNs"): only the outputs selected by a
+//! mask are computed, the others are copied from their nearest computed neighbor.
+//!
+//! The mask is described by a [`PerforationConfig`]: on average one output in `alpha` is
+//! computed, at positions chosen by a [`MaskFamily`].
+
+use crate::error::{Error, OrPanic, Result};
+use rand_core::SeedableRng;
+use rand_distr::Distribution;
+
+/// How the computed outputs are chosen.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum MaskFamily {
+    /// `ceil(alpha (i + u_i))` for `u_i` uniform in `[0, 1)` (the sequence of Figurnov et al.).
+    #[default]
+    PseudoRandom,
+    /// `floor(alpha i)`: a uniform grid.
+    Grid,
+    /// Every output independently, with probability `1 / alpha`.
+    Bernoulli,
+    /// `ceil(len / alpha)` outputs from a (randomly shifted) van der Corput sequence, the first
+    /// dimension of both the Sobol and Halton sequences.
+    LowDiscrepancy,
+    /// The `ceil(len / alpha)` most important outputs, by the energy of their input window.
+    Importance,
+    /// A user mask, `true` for the computed outputs (`alpha` and `seed` are ignored).
+    Custom(Box<[bool]>),
+}
+
+/// How the skipped outputs are filled in from the computed ones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Reconstruction {
+    /// A copy of the nearest computed output (the left one on ties).
+    #[default]
+    Nearest,
+    /// Linear interpolation between the computed outputs on either side.
+    Linear,
+    /// Cubic Hermite interpolation between the computed outputs on either side, with the slopes
+    /// of their other computed neighbors (Catmull-Rom on a non-uniform grid).
+    Cubic,
+    /// Zeroes.
+    Zero,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PerforationConfig {
+    /// One output in `alpha` is computed (on average); at least `1`.
+    pub alpha: f32,
+    pub seed: u64,
+    pub family: MaskFamily,
+    pub reconstruction: Reconstruction,
+}
+
+impl Default for PerforationConfig {
+    fn default() -> Self {
+        Self {
+            alpha: 1.5,
+            seed: 0,
+            family: MaskFamily::default(),
+            reconstruction: Reconstruction::default(),
+        }
+    }
+}
+
+impl PerforationConfig {
+    /// The default mask with a perforation rate of `rate` (the fraction of skipped outputs, in
+    /// `[0, 1)`), i.e. `alpha = 1 / (1 - rate)`.
+    pub fn with_rate(rate: f32) -> Self {
+        Self {
+            alpha: 1. / (1. - rate),
+            ..Default::default()
+        }
+    }
+
+    /// The fraction of skipped outputs.
+    pub fn rate(&self) -> f32 {
+        1. - 1. / self.alpha
+    }
+
+    fn check(&self) -> Result<()> {
+        if !(self.alpha >= 1. && self.alpha.is_finite()) {
+            return Err(Error::InvalidArgument(format!(
+                "perforation alpha of {} (must be at least 1)",
+                self.alpha
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// The mask of the computed outputs among `length`, with `importance(i)` the importance of
+    /// output `i` (only used by [`MaskFamily::Importance`]).
+    ///
+    /// At least one output is computed (the first one, if the mask is otherwise empty).
+    pub fn mask(&self, length: usize, importance: impl Fn(usize) -> f32) -> Result<Box<[bool]>> {
+        self.check()?;
+
+        // number of computed outputs, for the families that pick a fixed count
+        let count = (length as f32 / self.alpha).ceil() as usize;
+        let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(self.seed);
+
+        let mut mask = vec![false; length];
+        match &self.family {
+            MaskFamily::LowDiscrepancy => {
+                // Cranley-Patterson rotation
+                let shift = rand_distr::Uniform::new(0., 1.).sample(&mut rng);
+                let mut computed = 0;
+
+                // NOTE: `2 length` points cover every output
+                for i in 0u32.. {
+                    if computed == count.min(length) {
+                        break;
+                    }
+                    let x = (radical_inverse(i) + shift).fract();
+                    let t = ((x * length as f64) as usize).min(length - 1);
+                    if !mask[t] {
+                        mask[t] = true;
+                        computed += 1;
+                    }
+                }
+            }
+            MaskFamily::Importance => {
+                let mut order: Vec<_> = (0..length).map(|t| (importance(t), t)).collect();
+                order.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+                for &(_, t) in order.iter().take(count) {
+                    mask[t] = true;
+                }
+            }
+            MaskFamily::Custom(custom) => {
+                if custom.len() != length {
+                    return Err(Error::InvalidArgument(format!(
+                        "perforation mask of length {} for {length} outputs",
+                        custom.len()
+                    )));
+                }
+                mask.copy_from_slice(custom);
+            }
+            _ => {
+                let positions = MaskPositions::new(self)?;
+                for t in positions.take_while(|&t| t < length) {
+                    mask[t] = true;
+                }
+            }
+        }
+
+        // every output needs a computed neighbor
+        if length > 0 && !mask.contains(&true) {
+            mask[0] = true;
+        }
+
+        Ok(mask.into())
+    }
+}
+
+// van der Corput sequence in base 2
+fn radical_inverse(i: u32) -> f64 {
+    i.reverse_bits() as f64 / (1u64 << 32) as f64
+}
+
+/// The (non-decreasing) positions of the computed outputs, for the mask families that can be
+/// generated one position at a time.
+#[derive(Clone, Debug)]
+pub(super) struct MaskPositions {
+    rng: rand_xoshiro::Xoshiro256PlusPlus,
+    uniform: rand_distr::Uniform<f32>,
+    alpha: f32,
+    family: MaskFamily,
+    i: usize,
+}
+
+impl MaskPositions {
+    pub(super) fn new(config: &PerforationConfig) -> Result<Self> {
+        config.check()?;
+
+        match config.family {
+            MaskFamily::PseudoRandom | MaskFamily::Grid | MaskFamily::Bernoulli => {}
+            _ => {
+                return Err(Error::Unsupported(format!(
+                    "{:?} masks need the number of outputs in advance",
+                    config.family
+                )))
+            }
+        }
+
+        let uniform_range = (0., 1.);
+
+        Ok(Self {
+            rng: rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(config.seed),
+            uniform: rand_distr::Uniform::new(uniform_range.0, uniform_range.1),
+            alpha: config.alpha,
+            family: config.family.clone(),
+            i: 0,
+        })
+    }
+}
+
+impl Iterator for MaskPositions {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let i = self.i;
+        self.i += 1;
+
+        match self.family {
+            MaskFamily::Grid => Some((self.alpha * i as f32).floor() as usize),
+            MaskFamily::Bernoulli => {
+                let p = 1. / self.alpha;
+                while self.uniform.sample(&mut self.rng) >= p {
+                    self.i += 1;
+                }
+                Some(self.i - 1)
+            }
+            _ => {
+                let u = self.uniform.sample(&mut self.rng);
+                Some((self.alpha * (i as f32 + u)).ceil() as usize)
+            }
+        }
+    }
+}
+
+// a skipped output, and the computed outputs around it
+#[derive(Clone, Copy, Debug)]
+struct Gap {
+    t: usize,
+    nearest: usize,
+    // the closest computed outputs before and after `t`
+    previous: Option<usize>,
+    next: Option<usize>,
+    // the computed outputs before `previous` and after `next`
+    before: Option<usize>,
+    after: Option<usize>,
+}
+
+/// A perforation mask for one output size, with everything needed to reconstruct the skipped
+/// outputs, to be reused across calls.
+#[derive(Clone, Debug)]
+pub struct PerforationPlan {
+    mask: Box<[bool]>,
+    computed: Box<[usize]>,
+    gaps: Box<[Gap]>,
+    reconstruction: Reconstruction,
+}
+
+impl PerforationPlan {
+    /// The plan of `config` for `output_size` outputs. [`MaskFamily::Importance`] masks depend on
+    /// the input, and can't be planned.
+    pub fn new(config: &PerforationConfig, output_size: usize) -> Self {
+        Self::try_new(config, output_size).or_panic()
+    }
+
+    pub fn try_new(config: &PerforationConfig, output_size: usize) -> Result<Self> {
+        if config.family == MaskFamily::Importance {
+            return Err(Error::Unsupported(
+                "importance masks depend on the input".to_string(),
+            ));
+        }
+
+        Self::with_importance(config, output_size, |_| 0.)
+    }
+
+    pub(super) fn with_importance(
+        config: &PerforationConfig,
+        output_size: usize,
+        importance: impl Fn(usize) -> f32,
+    ) -> Result<Self> {
+        let mask = config.mask(output_size, importance)?;
+        let computed: Box<[usize]> = (0..output_size).filter(|&t| mask[t]).collect();
+
+        // the closest computed output at or before/after every step, in two passes
+        let mut previous = vec![None; output_size];
+        let mut last = None;
+        for t in 0..output_size {
+            if mask[t] {
+                last = Some(t);
+            }
+            previous[t] = last;
+        }
+        let mut next = vec![None; output_size];
+        let mut last = None;
+        for t in (0..output_size).rev() {
+            if mask[t] {
+                last = Some(t);
+            }
+            next[t] = last;
+        }
+
+        let gaps = (0..output_size)
+            .filter(|&t| !mask[t])
+            .map(|t| {
+                let (p, q) = (previous[t], next[t]);
+                // NOTE: the left one on ties
+                let nearest = match (p, q) {
+                    (Some(p), Some(q)) if q - t < t - p => q,
+                    (Some(p), _) => p,
+                    (None, q) => q.expect("at least one output is computed"),
+                };
+
+                Gap {
+                    t,
+                    nearest,
+                    previous: p,
+                    next: q,
+                    before: p.and_then(|p| p.checked_sub(1)).and_then(|i| previous[i]),
+                    after: q.and_then(|q| next.get(q + 1).copied().flatten()),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            mask,
+            computed,
+            gaps,
+            reconstruction: config.reconstruction,
+        })
+    }
+
+    pub fn output_size(&self) -> usize {
+        self.mask.len()
+    }
+
+    /// `true` for the computed outputs.
+    pub fn mask(&self) -> &[bool] {
+        &self.mask
+    }
+
+    /// The perforated `conv1d` of `input` with `kernel`, into `output`.
+    pub fn execute(&self, input: &[f32], kernel: &[f32], output: &mut [f32]) {
+        self.try_execute(input, kernel, output).or_panic()
+    }
+
+    pub fn try_execute(&self, input: &[f32], kernel: &[f32], output: &mut [f32]) -> Result<()> {
+        let output_size = super::output_size(input, kernel)?;
+        if output_size != self.output_size() || output.len() != self.output_size() {
+            return Err(Error::ShapeMismatch {
+                lhs: (self.output_size(), 1),
+                rhs: (output_size, output.len()),
+            });
+        }
+
+        for &t in self.computed.iter() {
+            output[t] = crate::math::inner_product(&input[t..], kernel, 0.);
+        }
+        self.reconstruct(output, 1);
+
+        Ok(())
+    }
+
+    /// Fills in the skipped rows (of `channels` values each) of `output`, from the computed ones.
+    pub(super) fn reconstruct(&self, output: &mut [f32], channels: usize) {
+        debug_assert_eq!(output.len(), self.output_size() * channels);
+
+        for gap in self.gaps.iter() {
+            let t = gap.t;
+            let (p, q) = match (self.reconstruction, gap.previous, gap.next) {
+                (Reconstruction::Zero, ..) => {
+                    output[t * channels..(t + 1) * channels].fill(0.);
+                    continue;
+                }
+                (Reconstruction::Linear | Reconstruction::Cubic, Some(p), Some(q)) => (p, q),
+                // nothing to interpolate with
+                _ => {
+                    let j = gap.nearest;
+                    output.copy_within(j * channels..(j + 1) * channels, t * channels);
+                    continue;
+                }
+            };
+
+            let h = (q - p) as f32;
+            let s = (t - p) as f32 / h;
+
+            for c in 0..channels {
+                let y = |i: usize| output[i * channels + c];
+                let (yp, yq) = (y(p), y(q));
+
+                output[t * channels + c] = match self.reconstruction {
+                    Reconstruction::Cubic => {
+                        // slopes (per step) at `p` and `q`, from their neighbors
+                        let slope = |a: usize, b: usize| (y(b) - y(a)) / (b - a) as f32;
+                        let mp = gap.before.map_or(slope(p, q), |a| slope(a, q));
+                        let mq = gap.after.map_or(slope(p, q), |b| slope(p, b));
+
+                        let (s2, s3) = (s * s, s * s * s);
+                        (2. * s3 - 3. * s2 + 1.) * yp
+                            + (s3 - 2. * s2 + s) * h * mp
+                            + (-2. * s3 + 3. * s2) * yq
+                            + (s3 - s2) * h * mq
+                    }
+                    _ => yp + s * (yq - yp),
+                };
+            }
+        }
+    }
+}
+
+pub fn conv1d(input: &[f32], kernel: &[f32]) -> Vec<f32> {
+    try_conv1d(input, kernel).or_panic()
+}
+
+pub fn try_conv1d(input: &[f32], kernel: &[f32]) -> Result<Vec<f32>> {
+    try_conv1d_with(input, kernel, &PerforationConfig::default())
+}
+
+/// `conv1d` with the perforation mask of `config`. This builds a [`PerforationPlan`] on every
+/// call: reuse one instead, unless the mask depends on the input.
+pub fn conv1d_with(input: &[f32], kernel: &[f32], config: &PerforationConfig) -> Vec<f32> {
+    try_conv1d_with(input, kernel, config).or_panic()
+}
+
+pub fn try_conv1d_with(
+    input: &[f32],
+    kernel: &[f32],
+    config: &PerforationConfig,
+) -> Result<Vec<f32>> {
+    let output_size = super::output_size(input, kernel)?;
+
+    // the energy of every input window (a running sum), only for importance masks
+    let mut energy = vec![];
+    if config.family == MaskFamily::Importance {
+        let mut sum: f32 = input[..kernel.len() - 1].iter().map(|x| x * x).sum();
+        for i in 0..output_size {
+            sum += input[i + kernel.len() - 1].powi(2);
+            energy.push(sum);
+            sum -= input[i].powi(2);
+        }
+    }
+
+    let plan = PerforationPlan::with_importance(config, output_size, |i| energy[i])?;
+
+    let mut output = vec![0.; output_size];
+    plan.try_execute(input, kernel, &mut output)?;
+
+    Ok(output)
+}
+
+#[test]
+fn baseline() {
+    let input: Box<[_]> = (0..20).map(|i| i as f32 + 1.).collect();
+    let kernel: Box<[_]> = (0..3).map(|_| 1.).collect();
+
+    let result = super::conv1d(&input, &kernel);
+    println!("conv1d: {result:?}");
+    assert_eq!(
+        result.as_slice(),
+        &[6., 9., 12., 15., 18., 21., 24., 27., 30., 33., 36., 39., 42., 45., 48., 51., 54., 57.]
+    );
+
+    // let mask_size = input.len() - kernel.len() + 1;
+    // let mask = pseudo_mask(mask_size);
+    // println!("mask[{mask_size}]: {mask:?}",);
+
+    let result = conv1d(&input, &kernel);
+    println!("perforation::conv1d: {result:?}");
+    assert_eq!(
+        result.as_slice(),
+        &[9., 9., 9., 15., 18., 21., 21., 27., 30., 30., 39., 39., 42., 45., 48., 48., 54., 57.]
+    );
+}
+
+#[test]
+fn reconstruction() {
+    let input: Vec<f32> = (0..12).map(|i| i as f32).collect();
+    let kernel = [1.];
+    let mask = PerforationConfig {
+        family: MaskFamily::Custom(
+            [
+                false, true, false, false, true, false, true, false, false, false, true, false,
+            ]
+            .into(),
+        ),
+        ..Default::default()
+    };
+    let with = |reconstruction| {
+        let config = PerforationConfig {
+            reconstruction,
+            ..mask.clone()
+        };
+        conv1d_with(&input, &kernel, &config)
+    };
+
+    assert_eq!(
+        with(Reconstruction::Nearest),
+        [1., 1., 1., 4., 4., 4., 6., 6., 6., 10., 10., 10.]
+    );
+    assert_eq!(
+        with(Reconstruction::Zero),
+        [0., 1., 0., 0., 4., 0., 6., 0., 0., 0., 10., 0.]
+    );
+    // a line is interpolated exactly (between computed outputs), by both
+    assert_eq!(
+        with(Reconstruction::Linear),
+        [1., 1., 2., 3., 4., 5., 6., 7., 8., 9., 10., 10.]
+    );
+    let cubic = with(Reconstruction::Cubic);
+    assert!(cubic[1..11]
+        .iter()
+        .zip(1..)
+        .all(|(y, t)| (y - t as f32).abs() < 1e-5));
+
+    // smooth signals are reconstructed better by interpolation
+    let input: Vec<f32> = (0..400).map(|i| (i as f32 * 0.05).sin()).collect();
+    let kernel = [0.25; 4];
+    let expected = super::conv1d(&input, &kernel);
+    let error = |reconstruction| {
+        let config = PerforationConfig {
+            alpha: 3.,
+            reconstruction,
+            ..Default::default()
+        };
+        let actual = conv1d_with(&input, &kernel, &config);
+        expected
+            .iter()
+            .zip(&actual)
+            .map(|(e, a)| (e - a).powi(2))
+            .sum::<f32>()
+    };
+    let (nearest, linear, cubic) = (
+        error(Reconstruction::Nearest),
+        error(Reconstruction::Linear),
+        error(Reconstruction::Cubic),
+    );
+    assert!(
+        cubic < linear && linear < nearest,
+        "{nearest} {linear} {cubic}"
+    );
+    assert!(nearest < error(Reconstruction::Zero));
+}
+
+#[test]
+fn plan() {
+    let input: Vec<f32> = (0..100).map(|i| (i as f32 * 0.2).cos()).collect();
+    let kernel = [0.5, 1., -0.25];
+
+    for reconstruction in [
+        Reconstruction::Nearest,
+        Reconstruction::Linear,
+        Reconstruction::Cubic,
+        Reconstruction::Zero,
+    ] {
+        let config = PerforationConfig {
+            alpha: 2.5,
+            reconstruction,
+            ..Default::default()
+        };
+        let plan = PerforationPlan::new(&config, 98);
+
+        // the same as building the mask every call, and reusable
+        let expected = conv1d_with(&input, &kernel, &config);
+        let mut output = vec![f32::NAN; 98];
+        for _ in 0..2 {
+            plan.execute(&input, &kernel, &mut output);
+            assert_eq!(output, expected);
+        }
+    }
+
+    // the nearest neighbor, the left one on ties
+    let config = PerforationConfig {
+        family: MaskFamily::Custom(
+            [
+                false, false, true, false, false, false, true, false, true, false,
+            ]
+            .into(),
+        ),
+        ..Default::default()
+    };
+    let plan = PerforationPlan::new(&config, 10);
+    let nearest: Vec<_> = plan.gaps.iter().map(|gap| (gap.t, gap.nearest)).collect();
+    assert_eq!(
+        nearest,
+        [(0, 2), (1, 2), (3, 2), (4, 2), (5, 6), (7, 6), (9, 8)]
+    );
+
+    let mut output = [0.; 8];
+    assert!(plan
+        .try_execute(&input[..10], &kernel, &mut output)
+        .is_err());
+    assert!(plan
+        .try_execute(&input[..11], &kernel, &mut [0.; 9])
+        .is_err());
+    assert!(PerforationPlan::try_new(
+        &PerforationConfig {
+            family: MaskFamily::Importance,
+            ..Default::default()
+        },
+        10
+    )
+    .is_err());
+}
+
+#[test]
+fn mask_families() {
+    let length = 1000;
+    let energy = |t: usize| (t % 10) as f32;
+
+    for family in [
+        MaskFamily::PseudoRandom,
+        MaskFamily::Grid,
+        MaskFamily::Bernoulli,
+        MaskFamily::LowDiscrepancy,
+        MaskFamily::Importance,
+    ] {
+        for alpha in [1., 2., 4.] {
+            let config = PerforationConfig {
+                alpha,
+                seed: 1,
+                family: family.clone(),
+                ..Default::default()
+            };
+            let mask = config.mask(length, energy).unwrap();
+            let computed = mask.iter().filter(|&&m| m).count() as f32;
+
+            // about one in `alpha`
+            let expected = length as f32 / alpha;
+            assert!(
+                (computed - expected).abs() < 0.1 * expected,
+                "{family:?} {alpha}"
+            );
+
+            // the same seed, the same mask
+            assert_eq!(config.mask(length, energy).unwrap(), mask);
+        }
+    }
+
+    let config = PerforationConfig {
+        alpha: 2.,
+        family: MaskFamily::Grid,
+        ..Default::default()
+    };
+    assert_eq!(
+        &*config.mask(6, |_| 0.).unwrap(),
+        [true, false, true, false, true, false]
+    );
+
+    // the most energetic windows
+    let config = PerforationConfig {
+        alpha: 10.,
+        family: MaskFamily::Importance,
+        ..Default::default()
+    };
+    let mask = config.mask(length, energy).unwrap();
+    assert!((0..length).all(|t| mask[t] == (t % 10 == 9)));
+
+    let input = [1., 2., 3., 4., 5.];
+    let custom = PerforationConfig {
+        family: MaskFamily::Custom([false, true, false].into()),
+        ..Default::default()
+    };
+    assert_eq!(conv1d_with(&input, &[1.; 3], &custom), [9., 9., 9.]);
+    let custom = PerforationConfig {
+        family: MaskFamily::Custom([false; 3].into()),
+        ..Default::default()
+    };
+    assert_eq!(conv1d_with(&input, &[1.; 3], &custom), [6., 6., 6.]);
+    let custom = PerforationConfig {
+        family: MaskFamily::Custom([true; 4].into()),
+        ..Default::default()
+    };
+    assert!(try_conv1d_with(&input, &[1.; 3], &custom).is_err());
+
+    assert!((PerforationConfig::with_rate(0.5).rate() - 0.5).abs() < 1e-6);
+    assert!(try_conv1d_with(&input, &[1.; 3], &PerforationConfig::with_rate(-1.)).is_err());
+    assert!(MaskPositions::new(&PerforationConfig {
+        family: MaskFamily::Importance,
+        ..Default::default()
+    })
+    .is_err());
+}
@@ -0,0 +1,328 @@
+//! Pooling and upsampling, over `(time, channels)` inputs like [`Conv1d`](super::Conv1d) (and
+//! `(rows, cols)` channels like [`Conv2d`](super::Conv2d) for 2-D pooling).
+//!
+//! The output sizes follow `Conv1d` with the pool size as the receptive field; padded samples are
+//! left out of the window (so average pooling divides by the number of actual samples, like
+//! TensorFlow).
+
+use super::layer::layout;
+use super::{Padding, PaddingMode};
+use crate::error::{Error, OrPanic, Result};
+use crate::matrix::{Matrix, MatrixView};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PoolKind {
+    #[default]
+    Max,
+    Average,
+}
+
+impl PoolKind {
+    fn pool(self, values: impl Iterator<Item = f32>) -> f32 {
+        match self {
+            PoolKind::Max => values.fold(f32::NEG_INFINITY, f32::max),
+            PoolKind::Average => {
+                let (sum, count) = values.fold((0., 0), |(sum, count), x| (sum + x, count + 1));
+                sum / count as f32
+            }
+        }
+    }
+}
+
+// the unpadded input indices of window `t`
+fn window(
+    t: usize,
+    (size, stride, left): (usize, usize, usize),
+    len: usize,
+) -> std::ops::Range<usize> {
+    let start = (t * stride) as isize - left as isize;
+    let end = start + size as isize;
+    start.clamp(0, len as isize) as usize..end.clamp(0, len as isize) as usize
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pool1d {
+    pub kind: PoolKind,
+    pub size: usize,
+    pub stride: usize,
+    pub padding: Padding,
+}
+
+impl Pool1d {
+    /// Non-overlapping max pooling (`stride = size`), without padding.
+    pub fn max(size: usize) -> Self {
+        Self {
+            kind: PoolKind::Max,
+            size,
+            stride: size,
+            padding: Padding::Valid,
+        }
+    }
+
+    /// Non-overlapping average pooling (`stride = size`), without padding.
+    pub fn average(size: usize) -> Self {
+        Self {
+            kind: PoolKind::Average,
+            ..Self::max(size)
+        }
+    }
+
+    /// Number of output steps for an input of length `time`.
+    pub fn output_size(&self, time: usize) -> Result<usize> {
+        Ok(self.layout(time)?.0)
+    }
+
+    fn layout(&self, time: usize) -> Result<(usize, usize)> {
+        if self.size == 0 || self.stride == 0 {
+            return Err(Error::InvalidArgument(format!(
+                "pool size {} and stride {} (must be positive)",
+                self.size, self.stride
+            )));
+        }
+
+        layout(
+            time,
+            self.size,
+            self.stride,
+            self.padding,
+            PaddingMode::Zeros,
+        )
+    }
+
+    /// Pools every channel of `input` of shape `(time, channels)`.
+    pub fn forward<'a>(&self, input: impl Into<MatrixView<'a>>) -> Matrix {
+        self.try_forward(input).or_panic()
+    }
+
+    pub fn try_forward<'a>(&self, input: impl Into<MatrixView<'a>>) -> Result<Matrix> {
+        let input = input.into();
+        let (time, channels) = input.shape();
+        let (output_size, left) = self.layout(time)?;
+
+        Ok(Matrix::from_fn(output_size, channels, |t, c| {
+            let window = window(t, (self.size, self.stride, left), time);
+            self.kind.pool(window.map(|i| input[(i, c)]))
+        }))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pool2d {
+    pub kind: PoolKind,
+    /// `(rows, cols)`.
+    pub size: (usize, usize),
+    pub stride: (usize, usize),
+    pub padding: (Padding, Padding),
+}
+
+impl Pool2d {
+    /// Non-overlapping max pooling (`stride = size`), without padding.
+    pub fn max(size: (usize, usize)) -> Self {
+        Self {
+            kind: PoolKind::Max,
+            size,
+            stride: size,
+            padding: (Padding::Valid, Padding::Valid),
+        }
+    }
+
+    /// Non-overlapping average pooling (`stride = size`), without padding.
+    pub fn average(size: (usize, usize)) -> Self {
+        Self {
+            kind: PoolKind::Average,
+            ..Self::max(size)
+        }
+    }
+
+    // the `Pool1d` of both axes
+    fn axes(&self) -> (Pool1d, Pool1d) {
+        let axis = |size, stride, padding| Pool1d {
+            kind: self.kind,
+            size,
+            stride,
+            padding,
+        };
+
+        (
+            axis(self.size.0, self.stride.0, self.padding.0),
+            axis(self.size.1, self.stride.1, self.padding.1),
+        )
+    }
+
+    /// Output shape for inputs of shape `(rows, cols)`.
+    pub fn output_size(&self, (m, n): (usize, usize)) -> Result<(usize, usize)> {
+        let (rows, cols) = self.axes();
+        Ok((rows.output_size(m)?, cols.output_size(n)?))
+    }
+
+    /// Pools every channel of `input`.
+    pub fn forward(&self, input: &[Matrix]) -> Vec<Matrix> {
+        self.try_forward(input).or_panic()
+    }
+
+    pub fn try_forward(&self, input: &[Matrix]) -> Result<Vec<Matrix>> {
+        let (rows, cols) = self.axes();
+
+        input
+            .iter()
+            .map(|x| {
+                let (m, n) = x.shape();
+                let ((output_rows, top), (output_cols, left)) = (rows.layout(m)?, cols.layout(n)?);
+
+                Ok(Matrix::from_fn(output_rows, output_cols, |i, j| {
+                    let row_window = window(i, (rows.size, rows.stride, top), m);
+                    let col_window = window(j, (cols.size, cols.stride, left), n);
+                    self.kind
+                        .pool(row_window.flat_map(|r| col_window.clone().map(move |c| x[(r, c)])))
+                }))
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Every input step repeated `factor` times (Keras' `UpSampling1D`).
+    #[default]
+    Nearest,
+    /// Linear interpolation between the input steps, at the centers of the output steps
+    /// (PyTorch's `align_corners=False`), clamped at the edges.
+    Linear,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Upsample1d {
+    pub factor: usize,
+    pub interpolation: Interpolation,
+}
+
+impl Upsample1d {
+    pub fn new(factor: usize, interpolation: Interpolation) -> Self {
+        Self {
+            factor,
+            interpolation,
+        }
+    }
+
+    /// Upsamples every channel of `input` of shape `(time, channels)` to
+    /// `(time * factor, channels)`.
+    pub fn forward<'a>(&self, input: impl Into<MatrixView<'a>>) -> Matrix {
+        self.try_forward(input).or_panic()
+    }
+
+    pub fn try_forward<'a>(&self, input: impl Into<MatrixView<'a>>) -> Result<Matrix> {
+        let input = input.into();
+        let (time, channels) = input.shape();
+
+        if self.factor == 0 {
+            return Err(Error::InvalidArgument(
+                "upsampling factor of 0 (must be positive)".to_string(),
+            ));
+        }
+        if time == 0 {
+            return Err(Error::EmptyInput);
+        }
+
+        let factor = self.factor;
+        Ok(match self.interpolation {
+            Interpolation::Nearest => {
+                Matrix::from_fn(time * factor, channels, |t, c| input[(t / factor, c)])
+            }
+            Interpolation::Linear => Matrix::from_fn(time * factor, channels, |t, c| {
+                let x = ((t as f32 + 0.5) / factor as f32 - 0.5).clamp(0., (time - 1) as f32);
+                let i = (x as usize).min(time - 1);
+                let s = x - i as f32;
+                let next = (i + 1).min(time - 1);
+                input[(i, c)] + s * (input[(next, c)] - input[(i, c)])
+            }),
+        })
+    }
+}
+
+#[test]
+fn pool1d() {
+    let input = Matrix::new(&[[1., -1.], [3., -2.], [2., -3.], [5., -4.], [4., -5.]]);
+
+    assert_eq!(
+        Pool1d::max(2).forward(&input),
+        Matrix::new(&[[3., -1.], [5., -3.]])
+    );
+    assert_eq!(
+        Pool1d::average(2).forward(&input),
+        Matrix::new(&[[2., -1.5], [3.5, -3.5]])
+    );
+
+    // padding is left out of the windows
+    let same = Pool1d {
+        padding: Padding::Same,
+        ..Pool1d::average(2)
+    };
+    assert_eq!(same.output_size(5).unwrap(), 3);
+    assert_eq!(
+        same.forward(&input),
+        Matrix::new(&[[2., -1.5], [3.5, -3.5], [4., -5.]])
+    );
+
+    let overlapping = Pool1d {
+        stride: 1,
+        ..Pool1d::max(3)
+    };
+    assert_eq!(
+        overlapping.forward(&input),
+        Matrix::new(&[[3., -1.], [5., -2.], [5., -3.]])
+    );
+
+    let causal = Pool1d {
+        stride: 1,
+        padding: Padding::Causal,
+        ..Pool1d::max(2)
+    };
+    assert_eq!(
+        causal.forward(&input),
+        Matrix::new(&[[1., -1.], [3., -1.], [3., -2.], [5., -3.], [5., -4.]])
+    );
+
+    assert!(Pool1d::max(0).try_forward(&input).is_err());
+    assert!(Pool1d::max(6).try_forward(&input).is_err());
+}
+
+#[test]
+fn pool2d() {
+    let x = Matrix::from_fn(4, 5, |i, j| (i * 5 + j) as f32);
+
+    let y = Pool2d::max((2, 2)).forward(std::slice::from_ref(&x));
+    assert_eq!(y[0], Matrix::new(&[[6., 8.], [16., 18.]]));
+
+    let y = Pool2d::average((2, 2)).forward(std::slice::from_ref(&x));
+    assert_eq!(y[0], Matrix::new(&[[3., 5.], [13., 15.]]));
+
+    let same = Pool2d {
+        padding: (Padding::Same, Padding::Same),
+        ..Pool2d::max((2, 2))
+    };
+    assert_eq!(same.output_size((4, 5)).unwrap(), (2, 3));
+    let y = same.forward(&[x.clone(), Matrix::zeroes(4, 5)]);
+    assert_eq!(y[0], Matrix::new(&[[6., 8., 9.], [16., 18., 19.]]));
+    assert_eq!(y[1], Matrix::zeroes(2, 3));
+}
+
+#[test]
+fn upsample1d() {
+    let input = Matrix::new(&[[0., 1.], [4., 1.]]);
+
+    let nearest = Upsample1d::new(2, Interpolation::Nearest);
+    assert_eq!(
+        nearest.forward(&input),
+        Matrix::new(&[[0., 1.], [0., 1.], [4., 1.], [4., 1.]])
+    );
+
+    let linear = Upsample1d::new(2, Interpolation::Linear);
+    assert_eq!(
+        linear.forward(&input),
+        Matrix::new(&[[0., 1.], [1., 1.], [3., 1.], [4., 1.]])
+    );
+
+    assert!(Upsample1d::new(0, Interpolation::Linear)
+        .try_forward(&input)
+        .is_err());
+}
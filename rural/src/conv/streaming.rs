@@ -0,0 +1,404 @@
+//! Sample-by-sample (or block-by-block) convolution for real-time processing.
+//!
+//! A [`StreamingConv1d`] keeps the last `receptive_field - 1` input frames in a ring buffer (zeroes
+//! after [`StreamingConv1d::reset`]), so its output for every new frame is the output of
+//! [`Conv1d::forward`] for the window ending at that frame. In other words, the streamed outputs
+//! are the batch outputs of the concatenated input, prefixed with `receptive_field - 1` zero frames.
+//!
+//! This is `Padding::Causal` with zero padding and a stride of `1`: the padding and stride of the
+//! layer's options are ignored.
+
+use super::perforation::{MaskPositions, PerforationConfig, Reconstruction};
+use super::Conv1d;
+use crate::error::{Error, OrPanic, Result};
+use crate::matrix::{Matrix, MatrixView, MatrixViewMut};
+
+// perforation state: which steps to compute, and the last computed outputs
+#[derive(Clone, Debug)]
+struct Perforation {
+    positions: MaskPositions,
+    // next computed step, counted from the first full window (like the batch output index)
+    next: usize,
+    step: usize,
+    reconstruction: Reconstruction,
+    // the (up to 3) last computed outputs, oldest first, and their frames
+    known: Box<[f32]>,
+    known_frames: [usize; 3],
+    known_count: usize,
+    frame: usize,
+}
+
+impl Perforation {
+    fn new(config: &PerforationConfig, channels: usize) -> Result<Self> {
+        let mut positions = MaskPositions::new(config)?;
+        let next = positions.next().unwrap_or(usize::MAX);
+
+        Ok(Self {
+            positions,
+            next,
+            step: 0,
+            reconstruction: config.reconstruction,
+            known: vec![0.; 3 * channels].into(),
+            known_frames: [0; 3],
+            known_count: 0,
+            frame: 0,
+        })
+    }
+
+    fn push(&mut self, output: &[f32]) {
+        let channels = output.len();
+        if self.known_count == 3 {
+            self.known.copy_within(channels.., 0);
+            self.known_frames.rotate_left(1);
+            self.known_count = 2;
+        }
+
+        let i = self.known_count;
+        self.known[i * channels..(i + 1) * channels].copy_from_slice(output);
+        self.known_frames[i] = self.frame;
+        self.known_count += 1;
+    }
+
+    // the causal counterpart of `perforation::reconstruct`, from the last computed outputs
+    fn extrapolate(&self, output: &mut [f32]) {
+        let channels = output.len();
+        let n = self.known_count;
+        let row = |i: usize| &self.known[i * channels..(i + 1) * channels];
+        let x = self.frame as f32;
+
+        match self.reconstruction {
+            Reconstruction::Zero => output.fill(0.),
+            Reconstruction::Cubic if n == 3 => {
+                // the Lagrange polynomial through the last three
+                let [a, b, c] = self.known_frames.map(|f| f as f32);
+                let la = (x - b) * (x - c) / ((a - b) * (a - c));
+                let lb = (x - a) * (x - c) / ((b - a) * (b - c));
+                let lc = (x - a) * (x - b) / ((c - a) * (c - b));
+                for (((y, ya), yb), yc) in output.iter_mut().zip(row(0)).zip(row(1)).zip(row(2)) {
+                    *y = la * ya + lb * yb + lc * yc;
+                }
+            }
+            Reconstruction::Linear | Reconstruction::Cubic if n >= 2 => {
+                let (a, b) = (
+                    self.known_frames[n - 2] as f32,
+                    self.known_frames[n - 1] as f32,
+                );
+                let s = (x - b) / (b - a);
+                for ((y, ya), yb) in output.iter_mut().zip(row(n - 2)).zip(row(n - 1)) {
+                    *y = yb + s * (yb - ya);
+                }
+            }
+            _ if n > 0 => output.copy_from_slice(row(n - 1)),
+            _ => output.fill(0.),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StreamingConv1d {
+    conv: Conv1d,
+    // the last `receptive_field - 1` input frames, `pos` is the oldest one
+    history: Matrix,
+    pos: usize,
+    // number of frames processed (up to `receptive_field`)
+    warmup: usize,
+    // the output of the last step
+    output: Box<[f32]>,
+    perforation: Option<Perforation>,
+    config: PerforationConfig,
+}
+
+impl StreamingConv1d {
+    pub fn new(conv: Conv1d) -> Self {
+        let history = Matrix::zeroes(conv.receptive_field() - 1, conv.in_channels());
+        let output = vec![0.; conv.out_channels()].into();
+
+        Self {
+            conv,
+            history,
+            pos: 0,
+            warmup: 0,
+            output,
+            perforation: None,
+            config: PerforationConfig::default(),
+        }
+    }
+
+    /// Streaming counterpart of [`Conv1d::perforated`]: computes the same steps (counted from the
+    /// first full window), but the others can only be reconstructed from the past, since the next
+    /// computed output isn't known yet: `Nearest` repeats the last computed output, `Linear`
+    /// extrapolates the last two, and `Cubic` the last three (with a parabola). The warm-up steps
+    /// are always computed.
+    pub fn perforated(conv: Conv1d) -> Self {
+        Self::perforated_with(conv, PerforationConfig::default())
+    }
+
+    /// [`StreamingConv1d::perforated`] with the mask and reconstruction of `config`; the mask
+    /// must be generated one step at a time: `PseudoRandom`, `Grid` or `Bernoulli`.
+    pub fn perforated_with(conv: Conv1d, config: PerforationConfig) -> Self {
+        Self::try_perforated_with(conv, config).or_panic()
+    }
+
+    pub fn try_perforated_with(conv: Conv1d, config: PerforationConfig) -> Result<Self> {
+        Ok(Self {
+            perforation: Some(Perforation::new(&config, conv.out_channels())?),
+            config,
+            ..Self::new(conv)
+        })
+    }
+
+    pub fn conv(&self) -> &Conv1d {
+        &self.conv
+    }
+
+    /// Clears the history (and restarts the perforation mask).
+    pub fn reset(&mut self) {
+        self.history.view_mut().fill(0.);
+        self.pos = 0;
+        self.warmup = 0;
+        self.output.fill(0.);
+
+        if let Some(perforation) = &mut self.perforation {
+            // NOTE: the config was checked on construction
+            *perforation = Perforation::new(&self.config, self.output.len()).or_panic();
+        }
+    }
+
+    // processes one frame (`x(c)` for every input channel) into `self.output`
+    fn step(&mut self, x: impl Fn(usize) -> f32) {
+        let h = self.history.shape().0;
+        let dilation = self.conv.options().dilation;
+        let kernel_size = self.conv.kernel_size();
+
+        let compute = match &mut self.perforation {
+            _ if self.warmup < h => true,
+            None => true,
+            Some(perforation) => {
+                let compute = perforation.step == perforation.next;
+                // NOTE: positions can repeat (rounding)
+                while perforation.next <= perforation.step {
+                    perforation.next = perforation.positions.next().unwrap_or(usize::MAX);
+                }
+                perforation.step += 1;
+                // the steps before the first computed one have nothing to repeat
+                compute || self.warmup == h
+            }
+        };
+
+        if compute {
+            let (history, pos) = (&self.history, self.pos);
+            self.conv.output_row(
+                |k, c| match (kernel_size - 1 - k) * dilation {
+                    0 => x(c),
+                    age => history[((pos + h - age) % h, c)],
+                },
+                &mut self.output,
+            );
+        }
+
+        if let Some(perforation) = &mut self.perforation {
+            if compute {
+                perforation.push(&self.output);
+            } else {
+                perforation.extrapolate(&mut self.output);
+            }
+            perforation.frame += 1;
+        }
+
+        if h > 0 {
+            for (c, y) in self.history[self.pos].iter_mut().enumerate() {
+                *y = x(c);
+            }
+            self.pos = (self.pos + 1) % h;
+        }
+        self.warmup = (self.warmup + 1).min(h + 1);
+    }
+
+    /// Processes one frame of `in_channels` samples into `out_channels` outputs.
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        self.try_process(input, output).or_panic()
+    }
+
+    pub fn try_process(&mut self, input: &[f32], output: &mut [f32]) -> Result<()> {
+        let (in_channels, out_channels) = (self.conv.in_channels(), self.conv.out_channels());
+        if input.len() != in_channels || output.len() != out_channels {
+            return Err(Error::ShapeMismatch {
+                lhs: (in_channels, out_channels),
+                rhs: (input.len(), output.len()),
+            });
+        }
+
+        self.step(|c| input[c]);
+        output.copy_from_slice(&self.output);
+
+        Ok(())
+    }
+
+    /// Processes `(n, in_channels)` frames into `(n, out_channels)` outputs.
+    pub fn process_block<'a>(&mut self, input: impl Into<MatrixView<'a>>, output: MatrixViewMut) {
+        self.try_process_block(input, output).or_panic()
+    }
+
+    pub fn try_process_block<'a>(
+        &mut self,
+        input: impl Into<MatrixView<'a>>,
+        mut output: MatrixViewMut,
+    ) -> Result<()> {
+        let input = input.into();
+        let (n, in_channels) = input.shape();
+
+        if in_channels != self.conv.in_channels() {
+            return Err(Error::ShapeMismatch {
+                lhs: (n, self.conv.in_channels()),
+                rhs: input.shape(),
+            });
+        }
+        if output.shape() != (n, self.conv.out_channels()) {
+            return Err(Error::ShapeMismatch {
+                lhs: (n, self.conv.out_channels()),
+                rhs: output.shape(),
+            });
+        }
+
+        for t in 0..n {
+            self.step(|c| input[(t, c)]);
+            for (o, &y) in self.output.iter().enumerate() {
+                output[(t, o)] = y;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn streaming() {
+    use super::Conv1dOptions;
+    use rand_core::SeedableRng;
+
+    let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+
+    let options = Conv1dOptions {
+        dilation: 2,
+        groups: 2,
+        ..Default::default()
+    };
+    let weights = Matrix::normal(3 * 2, 6, 0., 1., &mut rng);
+    let conv = Conv1d::new(4, 3, weights, &[0.1, 0.2, 0.3, 0.4, 0.5, 0.6], options);
+    let h = conv.receptive_field() - 1;
+
+    let input = Matrix::normal(40, 4, 0., 1., &mut rng);
+
+    // the batch output with `h` leading zero frames
+    let mut padded = Matrix::zeroes(40 + h, 4);
+    padded.slice_mut(h.., ..).copy_from(&input);
+    let expected = conv.forward(&padded);
+
+    let mut stream = StreamingConv1d::new(conv.clone());
+    let mut output = Matrix::zeroes(40, 6);
+    for t in 0..40 {
+        stream.process(&input[t], &mut output[t]);
+    }
+    assert!(expected.max_abs_error(&output) < 1e-5);
+
+    // in blocks of any size, after a reset
+    stream.reset();
+    let mut blocks = Matrix::zeroes(40, 6);
+    for (start, end) in [(0, 1), (1, 8), (8, 8), (8, 29), (29, 40)] {
+        stream.process_block(
+            input.slice(start..end, ..),
+            blocks.slice_mut(start..end, ..),
+        );
+    }
+    assert_eq!(blocks, output);
+
+    assert!(stream
+        .try_process_block(&input, Matrix::zeroes(40, 5).view_mut())
+        .is_err());
+    assert!(stream.try_process(&[0.; 3], &mut [0.; 6]).is_err());
+
+    // `conv1d`, once the kernel is filled
+    let x: Vec<f32> = (0..32).map(|t| (t as f32).sin()).collect();
+    let kernel = [0.5, -1., 2., 0.25];
+    let expected = super::conv1d(&x, &kernel);
+
+    let mut stream = StreamingConv1d::new(Conv1d::from_kernel(&kernel));
+    let mut y = [0.];
+    for (t, &x) in x.iter().enumerate() {
+        stream.process(&[x], &mut y);
+        if t >= 3 {
+            assert!((y[0] - expected[t - 3]).abs() < 1e-5);
+        }
+    }
+}
+
+#[test]
+fn perforated_streaming() {
+    use rand_core::SeedableRng;
+
+    let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+
+    let weights = Matrix::normal(5 * 2, 3, 0., 1., &mut rng);
+    let conv = Conv1d::new(2, 5, weights, &[0.; 3], Default::default());
+    let h = conv.receptive_field() - 1;
+
+    let input = Matrix::normal(60, 2, 0., 1., &mut rng);
+    let exact = conv.forward(&input);
+    let perforated = conv.perforated(&input);
+
+    let mut stream = StreamingConv1d::perforated(conv);
+    let mut output = Matrix::zeroes(60, 3);
+    stream.process_block(&input, output.view_mut());
+
+    // the same steps are computed, the others repeat the last one
+    let mut computed = 0;
+    for t in 0..exact.shape().0 {
+        let y = &output[t + h];
+        if perforated[t] == exact[t] {
+            assert_eq!(y, &exact[t]);
+            computed = t;
+        } else {
+            assert_eq!(y, &exact[computed]);
+        }
+    }
+    assert!(computed > 0);
+
+    // only the masks generated step by step can be streamed
+    use super::perforation::MaskFamily;
+    let config = |family| PerforationConfig {
+        alpha: 3.,
+        family,
+        ..Default::default()
+    };
+    let mut stream =
+        StreamingConv1d::perforated_with(stream.conv().clone(), config(MaskFamily::Grid));
+    stream.process_block(&input, output.view_mut());
+    assert_eq!(&output[h + 3], &exact[3]);
+    assert_eq!(&output[h + 4], &exact[3]);
+
+    // a ramp is extrapolated exactly, and zeroes are zeroes
+    let ramp = Matrix::from_fn(30, 1, |t, _| t as f32);
+    let streamed = |reconstruction| {
+        let config = PerforationConfig {
+            reconstruction,
+            ..config(MaskFamily::PseudoRandom)
+        };
+        let mut stream = StreamingConv1d::perforated_with(Conv1d::from_kernel(&[1.]), config);
+        let mut output = Matrix::zeroes(30, 1);
+        stream.process_block(&ramp, output.view_mut());
+        output
+    };
+    for reconstruction in [Reconstruction::Linear, Reconstruction::Cubic] {
+        let output = streamed(reconstruction);
+        // once there are enough computed outputs
+        assert!(ramp.slice(10.., ..).max_abs_error(output.slice(10.., ..)) < 1e-4);
+    }
+    let zero = streamed(Reconstruction::Zero);
+    assert!((0..30).all(|t| [0., t as f32].contains(&zero[(t, 0)])));
+    assert!((1..30).any(|t| zero[(t, 0)] == 0.));
+    assert!(StreamingConv1d::try_perforated_with(
+        stream.conv().clone(),
+        config(MaskFamily::Importance)
+    )
+    .is_err());
+}
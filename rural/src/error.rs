@@ -0,0 +1,100 @@
+//! Errors returned by the `try_` variants of operations that can fail on malformed inputs (e.g.
+//! shapes read from a model file). The panicking variants panic with the same message.
+
+use core::fmt;
+use std::sync::Arc;
+
+#[derive(Clone, Debug)]
+pub enum Error {
+    /// The shapes of two operands are incompatible (e.g. the inner dimensions of a product).
+    ShapeMismatch {
+        lhs: (usize, usize),
+        rhs: (usize, usize),
+    },
+    /// A range `start..end` is out of bounds for a dimension (or buffer) of length `len`.
+    OutOfBounds {
+        start: usize,
+        end: usize,
+        len: usize,
+    },
+    /// The operation needs at least one element.
+    EmptyInput,
+    /// A `factor` outside of `[0, 1]`.
+    InvalidFactor(f32),
+    /// Any other invalid parameter.
+    InvalidArgument(String),
+    /// Reading or writing a file failed (shared, so errors stay `Clone`).
+    Io(Arc<std::io::Error>),
+    /// A file is malformed.
+    Format(String),
+    /// A valid file uses a feature that isn't supported (e.g. an unsupported dtype).
+    Unsupported(String),
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::ShapeMismatch { lhs, rhs } => {
+                write!(f, "Matrix dimensions mismatch: {lhs:?} vs {rhs:?}")
+            }
+            Error::OutOfBounds { start, end, len } => {
+                write!(f, "Slice {start}..{end} out of bounds for length {len}")
+            }
+            Error::EmptyInput => write!(f, "Empty input"),
+            Error::InvalidFactor(factor) => write!(f, "Invalid factor {factor} (not in [0, 1])"),
+            Error::InvalidArgument(message) => write!(f, "Invalid argument: {message}"),
+            Error::Io(error) => write!(f, "I/O error: {error}"),
+            Error::Format(message) => write!(f, "Malformed file: {message}"),
+            Error::Unsupported(message) => write!(f, "Unsupported: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(Arc::new(error))
+    }
+}
+
+// `a·b` is defined
+pub(crate) fn check_matmul(a: (usize, usize), b: (usize, usize)) -> Result<()> {
+    if a.1 == b.0 {
+        Ok(())
+    } else {
+        Err(Error::ShapeMismatch { lhs: a, rhs: b })
+    }
+}
+
+pub(crate) fn check_factor(factor: f32) -> Result<()> {
+    if (0. ..=1.).contains(&factor) {
+        Ok(())
+    } else {
+        Err(Error::InvalidFactor(factor))
+    }
+}
+
+/// Used by the panicking variants of fallible operations.
+pub(crate) trait OrPanic<T> {
+    fn or_panic(self) -> T;
+}
+
+impl<T> OrPanic<T> for Result<T> {
+    #[track_caller]
+    fn or_panic(self) -> T {
+        match self {
+            Ok(value) => value,
+            Err(error) => panic!("{error}"),
+        }
+    }
+}
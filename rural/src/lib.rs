@@ -2,5 +2,8 @@ extern crate core;
 
 pub mod activation;
 pub mod conv;
+pub mod error;
 pub mod math;
 pub mod matrix;
+
+pub use error::{Error, Result};
@@ -1,8 +1,38 @@
+use crate::error::{check_matmul, Error, OrPanic, Result};
 use core::fmt;
-use rand_core::SeedableRng;
+use rand_core::{RngCore, SeedableRng};
 use rand_distr::Distribution;
 use std::ops::{Index, IndexMut};
 
+mod bound;
+mod init;
+mod lowrank;
+mod npy;
+mod ops;
+mod pq;
+mod prepared;
+mod quant;
+mod reduce;
+mod sampling;
+mod sketch;
+mod sparse;
+mod strassen;
+mod view;
+
+pub use bound::ErrorBound;
+pub use lowrank::{LowRank, Rank, Svd, SvdOptions};
+pub use npy::{Dtype, Order};
+pub use ops::broadcast_shape;
+pub use pq::{Maddness, PqOptions};
+pub use prepared::{FixedPlan, PreparedMatrix};
+pub use quant::{Granularity, Observer, QuantParams, QuantizedMatrix, Scheme};
+pub use reduce::{Axis, Norm};
+pub use sampling::Sampling;
+pub use sketch::Sketch;
+pub use sparse::{CscMatrix, CsrMatrix, Pruning};
+pub use strassen::Variant;
+pub use view::{MatrixView, MatrixViewMut};
+
 // TODO(toms): create custom Debug formatter that has sub-arrays
 #[derive(Clone)]
 pub struct Matrix {
@@ -18,6 +48,61 @@ impl Matrix {
         }
     }
 
+    /// Row-major `data` of the given shape.
+    pub fn from_vec(shape: (usize, usize), data: Vec<f32>) -> Self {
+        Self::try_from_vec(shape, data).or_panic()
+    }
+
+    pub fn try_from_vec((m, n): (usize, usize), data: Vec<f32>) -> Result<Self> {
+        if data.len() != m * n {
+            return Err(Error::InvalidArgument(format!(
+                "{} elements for shape {:?}",
+                data.len(),
+                (m, n)
+            )));
+        }
+
+        Ok(Self {
+            data: data.into(),
+            shape: (m, n),
+        })
+    }
+
+    /// Rows of equal length (`(0, 0)` if there are none).
+    pub fn from_rows<R: AsRef<[f32]>>(rows: &[R]) -> Self {
+        Self::try_from_rows(rows).or_panic()
+    }
+
+    pub fn try_from_rows<R: AsRef<[f32]>>(rows: &[R]) -> Result<Self> {
+        let n = rows.first().map_or(0, |row| row.as_ref().len());
+
+        let mut data = Vec::with_capacity(rows.len() * n);
+        for row in rows {
+            let row = row.as_ref();
+            if row.len() != n {
+                return Err(Error::ShapeMismatch {
+                    lhs: (1, n),
+                    rhs: (1, row.len()),
+                });
+            }
+            data.extend_from_slice(row);
+        }
+
+        Self::try_from_vec((rows.len(), n), data)
+    }
+
+    /// Element `(i, j)` is `f(i, j)`.
+    pub fn from_fn(m: usize, n: usize, mut f: impl FnMut(usize, usize) -> f32) -> Self {
+        Self {
+            data: (0..m * n).map(|k| f(k / n, k % n)).collect(),
+            shape: (m, n),
+        }
+    }
+
+    pub fn identity(n: usize) -> Self {
+        Self::from_fn(n, n, |i, j| if i == j { 1. } else { 0. })
+    }
+
     pub fn fill(m: usize, n: usize, value: f32) -> Self {
         Self {
             data: (0..m * n).map(|_| value).collect(),
@@ -33,13 +118,21 @@ impl Matrix {
         Self::fill(m, n, 0.)
     }
 
-    // TODO(toms): add support for passing rand-num-generator (in order to control with seed)
+    /// Uniform in `[-1, 1)`, from a seeded Xoshiro256++.
     pub fn random(m: usize, n: usize, seed: u64) -> Self {
         let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(seed);
-        let dist = rand_distr::Uniform::new(-1., 1.);
+        Self::random_with(m, n, &mut rng, rand_distr::Uniform::new(-1., 1.))
+    }
 
+    /// I.i.d. samples of `dist`, drawn in row-major order.
+    pub fn random_with(
+        m: usize,
+        n: usize,
+        rng: &mut impl RngCore,
+        dist: impl Distribution<f32>,
+    ) -> Self {
         Self {
-            data: (0..m * n).map(|_| dist.sample(&mut rng)).collect(),
+            data: (0..m * n).map(|_| dist.sample(rng)).collect(),
             shape: (m, n),
         }
     }
@@ -47,20 +140,36 @@ impl Matrix {
     pub fn shape(&self) -> (usize, usize) {
         self.shape
     }
+
+    /// Element `(i, j)`, if it is in bounds.
+    pub fn get(&self, (i, j): (usize, usize)) -> Option<f32> {
+        let (m, n) = self.shape;
+        (i < m && j < n).then(|| self.data[i * n + j])
+    }
 }
 
 impl Index<(usize, usize)> for Matrix {
     type Output = f32;
     fn index(&self, (i, j): (usize, usize)) -> &Self::Output {
         let (m, n) = self.shape;
-        debug_assert!(i < m && j < n);
+        assert!(
+            i < m && j < n,
+            "Index {:?} out of bounds for shape {:?}",
+            (i, j),
+            (m, n)
+        );
         &self.data[i * n..][j]
     }
 }
 impl IndexMut<(usize, usize)> for Matrix {
     fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut Self::Output {
         let (m, n) = self.shape;
-        debug_assert!(i < m && j < n);
+        assert!(
+            i < m && j < n,
+            "Index {:?} out of bounds for shape {:?}",
+            (i, j),
+            (m, n)
+        );
         &mut self.data[i * n..][j]
     }
 }
@@ -113,103 +222,105 @@ impl PartialEq for Matrix {
 
 impl Matrix {
     // simple matrix multiplication (row-major order)
-    pub fn matmul(&self, other: &Matrix) -> Matrix {
-        debug_assert_eq!(self.shape.1, other.shape.0, "Matrix dimensions mismatch");
-
-        let (m, n) = self.shape;
-        let (_n, p) = other.shape;
-
-        // TODO(toms): lift this allocation out?
-        let mut result = Matrix::zeroes(m, p);
-
-        for k in 0..n {
-            for i in 0..m {
-                for j in 0..p {
-                    result[i][j] += self[i][k] * other[k][j];
-                }
-            }
-        }
-
-        result
+    pub fn matmul<'b>(&self, other: impl Into<MatrixView<'b>>) -> Matrix {
+        self.view().matmul(other)
     }
 
-    pub fn add(&self, other: &Matrix) -> Matrix {
-        debug_assert_eq!(self.shape, other.shape, "Matrix dimensions mismatch");
-
-        let (m, n) = self.shape;
-
-        let mut result = Matrix::zeroes(m, n);
-
-        for i in 0..m {
-            for j in 0..n {
-                result[(i, j)] = self[(i, j)] + other[(i, j)];
-            }
-        }
-
-        result
+    pub fn try_matmul<'b>(&self, other: impl Into<MatrixView<'b>>) -> Result<Matrix> {
+        self.view().try_matmul(other)
     }
 }
 
-fn indices(x: &[f32], c: usize) -> Box<[usize]> {
-    let n = x.len();
-    let mut indices: Vec<usize> = (0..n).collect();
-
-    indices.sort_unstable_by(|&i, &j| {
-        if x[i] < x[j] {
-            std::cmp::Ordering::Less
-        } else {
-            std::cmp::Ordering::Greater
-        }
-    });
+impl MatrixView<'_> {
+    pub fn matmul<'b>(&self, other: impl Into<MatrixView<'b>>) -> Matrix {
+        self.try_matmul(other).or_panic()
+    }
 
-    indices.split_off(n - c).into_boxed_slice()
-}
+    pub fn try_matmul<'b>(&self, other: impl Into<MatrixView<'b>>) -> Result<Matrix> {
+        let other = other.into();
+        check_matmul(self.shape(), other.shape())?;
 
-// TODO(toms): separate this into its own module?
-impl Matrix {
-    // 'random' matrix multiplication (row-major order)
-    pub fn rand_matmul(&self, other: &Matrix, factor: f32) -> Matrix {
         let (m, n) = self.shape();
         let (_n, p) = other.shape();
 
-        let a_col_norm: Box<[f32]> = (0..n)
-            .map(|n| (0..m).map(|m| self[(m, n)].powi(2)).sum::<f32>().sqrt())
-            .collect();
-
-        let b_row_norm: Box<[f32]> = (0..n)
-            .map(|n| (0..p).map(|p| other[(n, p)].powi(2)).sum::<f32>().sqrt())
-            .collect();
-
-        assert_eq!(a_col_norm.len(), n);
-        assert_eq!(a_col_norm.len(), b_row_norm.len());
-
-        let sum_norm = crate::math::inner_product(&a_col_norm, &b_row_norm, 0.);
-
-        let prob: Box<[f32]> = (0..n)
-            .into_iter()
-            .map(|n| a_col_norm[n] * b_row_norm[n] / sum_norm)
-            .collect();
-
-        // TODO(toms): assert that elements in `prob` sum to 1.
-
-        debug_assert!(0. <= factor && factor <= 1.);
-        let c = ((n as f32 * factor).ceil() as usize).min(n);
-        // println!("n={n} c={c}");
-
         // TODO(toms): lift this allocation out?
         let mut result = Matrix::zeroes(m, p);
 
-        for &t in indices(&prob, c).into_iter() {
-            let c = c as f32;
-            let pt = 1. / (c * prob[t]);
-
+        for k in 0..n {
             for i in 0..m {
-                for j in 0..p {
-                    result[(i, j)] += pt * self[(i, t)] * other[(t, j)];
+                let a = self[(i, k)];
+                match other.row(k) {
+                    Some(row) => {
+                        for (y, b) in result[i].iter_mut().zip(row) {
+                            *y += a * b;
+                        }
+                    }
+                    None => {
+                        for j in 0..p {
+                            result[i][j] += a * other[(k, j)];
+                        }
+                    }
                 }
             }
         }
 
-        result
+        Ok(result)
     }
 }
+
+#[test]
+fn constructors() {
+    let a = Matrix::new(&[[1., 2., 3.], [4., 5., 6.]]);
+
+    assert_eq!(Matrix::from_vec((2, 3), vec![1., 2., 3., 4., 5., 6.]), a);
+    assert_eq!(Matrix::from_rows(&[[1., 2., 3.], [4., 5., 6.]]), a);
+    assert_eq!(Matrix::from_rows(&[&a[0], &a[1]]), a);
+    assert_eq!(Matrix::from_fn(2, 3, |i, j| (3 * i + j + 1) as f32), a);
+    assert_eq!(
+        Matrix::identity(3).matmul(a.transpose()),
+        a.transpose().to_matrix()
+    );
+
+    assert!(Matrix::try_from_vec((2, 2), vec![1., 2., 3.]).is_err());
+    assert!(Matrix::try_from_rows(&[vec![1., 2.], vec![3.]]).is_err());
+    assert_eq!(Matrix::from_rows::<[f32; 0]>(&[]).shape(), (0, 0));
+
+    // `random` is `random_with` a seeded Xoshiro256++
+    let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(7);
+    let b = Matrix::random_with(4, 5, &mut rng, rand_distr::Uniform::new(-1., 1.));
+    assert_eq!(b, Matrix::random(4, 5, 7));
+}
+
+#[test]
+fn shape_errors() {
+    use crate::Error;
+
+    let a = Matrix::random(2, 3, 0);
+    let b = Matrix::random(2, 3, 1);
+
+    assert!(matches!(
+        a.try_matmul(&b),
+        Err(Error::ShapeMismatch {
+            lhs: (2, 3),
+            rhs: (2, 3)
+        })
+    ));
+    assert!(a.try_matmul(b.transpose()).is_ok());
+    assert!(matches!(
+        a.try_rand_matmul(b.transpose(), 1.5),
+        Err(Error::InvalidFactor(_))
+    ));
+
+    let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+    let mut sketch =
+        |factor| a.try_sketch_matmul(b.transpose(), factor, Sketch::Gaussian, &mut rng);
+    assert!(matches!(sketch(1.5), Err(Error::InvalidFactor(_))));
+    assert_eq!(sketch(0.).unwrap(), Matrix::zeroes(2, 2));
+
+    assert!(matches!(
+        a.try_slice(..3, ..),
+        Err(Error::OutOfBounds { len: 2, .. })
+    ));
+    assert_eq!(a.get((1, 2)), Some(a[(1, 2)]));
+    assert_eq!(a.get((2, 0)), None);
+}
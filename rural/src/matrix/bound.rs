@@ -0,0 +1,372 @@
+//! Error estimates for [`Matrix::rand_matmul_with`], so `factor` can be chosen up front instead of
+//! measuring the error against a full `matmul` offline.
+//!
+//! With `x_k = |A^(k)| |B_(k)|` (the Frobenius norm of the `k`-th outer product) and `c` samples:
+//! * i.i.d. sampling with probabilities `p_k`: `E[|AB - CR|²] ≤ Σ_k x_k² / (c p_k)`, which is
+//!   `(Σ_k x_k)² / c` for the optimal probabilities (Drineas, Kannan, Mahoney; Lemma 4)
+//! * Poisson sampling with inclusion probabilities `q_k`: `E[|AB - CR|²] ≤ Σ_k x_k² (1 - q_k) / q_k`
+//! * top-c: `|AB - CR|² ≤ (Σ_{k ∉ S} x_k)²` (deterministic)
+//!
+//! (all norms are Frobenius norms; the bounds drop the `-|AB|² / c` term, which would require the
+//! exact product)
+
+use super::prepared::PreparedMatrix;
+use super::sampling::{
+    inclusion_probabilities, indices, num_samples, outer_products, probabilities, Sampling,
+};
+use super::{Matrix, MatrixView, Norm};
+use crate::error::{check_factor, check_matmul, Error, OrPanic, Result};
+use rand_core::RngCore;
+
+/// Theoretical error bounds of randomized matrix multiplication for a given pair of operands.
+#[derive(Clone, Debug)]
+pub struct ErrorBound {
+    // `|A^(k)| |B_(k)|` for every outer product
+    norms: Box<[f32]>,
+    // `|A|_F |B|_F`
+    frobenius: f32,
+}
+
+impl ErrorBound {
+    pub fn new<'a, 'b>(a: impl Into<MatrixView<'a>>, b: impl Into<MatrixView<'b>>) -> Self {
+        Self::try_new(a, b).or_panic()
+    }
+
+    pub fn try_new<'a, 'b>(
+        a: impl Into<MatrixView<'a>>,
+        b: impl Into<MatrixView<'b>>,
+    ) -> Result<Self> {
+        let (a, b) = (a.into(), b.into());
+        check_matmul(a.shape(), b.shape())?;
+
+        Ok(Self::from_norms(
+            &a.col_norms(Norm::L2).data,
+            &b.row_norms(Norm::L2).data,
+        ))
+    }
+
+    pub fn prepared<'a>(a: impl Into<MatrixView<'a>>, b: &PreparedMatrix) -> Self {
+        Self::try_prepared(a, b).or_panic()
+    }
+
+    pub fn try_prepared<'a>(a: impl Into<MatrixView<'a>>, b: &PreparedMatrix) -> Result<Self> {
+        let a = a.into();
+        check_matmul(a.shape(), b.shape())?;
+
+        Ok(Self::from_norms(&a.col_norms(Norm::L2).data, b.row_norms()))
+    }
+
+    fn from_norms(a_col_norm: &[f32], b_row_norm: &[f32]) -> Self {
+        assert_eq!(a_col_norm.len(), b_row_norm.len());
+
+        let norms: Box<[f32]> = a_col_norm
+            .iter()
+            .zip(b_row_norm)
+            .map(|(a, b)| a * b)
+            .collect();
+
+        let sq = |x: &[f32]| x.iter().map(|x| x * x).sum::<f32>();
+        let frobenius = (sq(a_col_norm) * sq(b_row_norm)).sqrt();
+
+        Self { norms, frobenius }
+    }
+
+    /// Upper bound on the expected squared Frobenius error when evaluating `c` outer products.
+    pub fn expected_for_samples(&self, sampling: Sampling, c: usize) -> f32 {
+        let n = self.norms.len();
+        let x = &self.norms;
+
+        let sum: f32 = x.iter().sum();
+        let sum_sq: f32 = x.iter().map(|x| x * x).sum();
+
+        if sum == 0. {
+            return 0.;
+        }
+
+        match sampling {
+            Sampling::WithReplacement if c == 0 => sum * sum,
+            Sampling::WithReplacement => sum * sum / c as f32,
+            Sampling::Uniform if c == 0 => sum * sum,
+            Sampling::Uniform => n as f32 * sum_sq / c as f32,
+            Sampling::WithoutReplacement if c == 0 => sum * sum,
+            Sampling::WithoutReplacement => {
+                let prob: Box<[f32]> = x.iter().map(|x| x / sum).collect();
+                inclusion_probabilities(&prob, c)
+                    .iter()
+                    .zip(x.iter())
+                    .map(|(&q, x)| if q > 0. { x * x * (1. - q) / q } else { x * x })
+                    .sum()
+            }
+            Sampling::TopC => {
+                let kept: f32 = indices(x, c).iter().map(|&k| x[k]).sum();
+                (sum - kept).max(0.).powi(2)
+            }
+        }
+    }
+
+    /// Upper bound on the expected squared Frobenius error at the given `factor`.
+    pub fn expected(&self, sampling: Sampling, factor: f32) -> f32 {
+        self.try_expected(sampling, factor).or_panic()
+    }
+
+    pub fn try_expected(&self, sampling: Sampling, factor: f32) -> Result<f32> {
+        check_factor(factor)?;
+
+        Ok(self.expected_for_samples(sampling, num_samples(self.norms.len(), factor)))
+    }
+
+    /// Frobenius error that is exceeded with probability at most `delta`.
+    ///
+    /// This is Markov's inequality applied to [`ErrorBound::expected`], tightened for
+    /// [`Sampling::WithReplacement`] by the concentration bound `η |A|_F |B|_F / sqrt(c)` with
+    /// `η = 1 + sqrt(8 ln(1 / δ))` (DKM; Theorem 1).
+    pub fn confidence(&self, sampling: Sampling, factor: f32, delta: f32) -> f32 {
+        self.try_confidence(sampling, factor, delta).or_panic()
+    }
+
+    pub fn try_confidence(&self, sampling: Sampling, factor: f32, delta: f32) -> Result<f32> {
+        if !(0. < delta && delta < 1.) {
+            return Err(Error::InvalidArgument(format!(
+                "delta {delta} (not in (0, 1))"
+            )));
+        }
+
+        let markov = (self.try_expected(sampling, factor)? / delta).sqrt();
+
+        let c = num_samples(self.norms.len(), factor);
+        Ok(match sampling {
+            Sampling::WithReplacement if c > 0 => {
+                let eta = 1. + (8. * (1. / delta).ln()).sqrt();
+                markov.min(eta * self.frobenius / (c as f32).sqrt())
+            }
+            _ => markov,
+        })
+    }
+
+    /// Smallest number of samples whose expected squared Frobenius error is at most `target`, if
+    /// any (i.i.d. sampling can't reach an arbitrary `target` with `c <= n`).
+    pub fn samples_for(&self, sampling: Sampling, target: f32) -> Option<usize> {
+        let n = self.norms.len();
+
+        if self.expected_for_samples(sampling, n) > target {
+            return None;
+        }
+
+        // NOTE: the bounds are non-increasing in `c`
+        let (mut lo, mut hi) = (0, n);
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            if self.expected_for_samples(sampling, mid) <= target {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+
+        Some(lo)
+    }
+
+    /// Smallest `factor` whose expected squared Frobenius error is at most `target`, if any.
+    pub fn factor_for(&self, sampling: Sampling, target: f32) -> Option<f32> {
+        let n = self.norms.len().max(1);
+        self.samples_for(sampling, target)
+            .map(|c| c as f32 / n as f32)
+    }
+}
+
+impl Matrix {
+    pub fn rand_matmul_error_bound<'b>(&self, other: impl Into<MatrixView<'b>>) -> ErrorBound {
+        ErrorBound::new(self, other)
+    }
+
+    pub fn try_rand_matmul_error_bound<'b>(
+        &self,
+        other: impl Into<MatrixView<'b>>,
+    ) -> Result<ErrorBound> {
+        ErrorBound::try_new(self, other)
+    }
+
+    /// Same as [`Matrix::rand_matmul_with`], but also returns a runtime estimate of the squared
+    /// Frobenius error, computed from the sampled terms only.
+    pub fn rand_matmul_with_estimate<'b>(
+        &self,
+        other: impl Into<MatrixView<'b>>,
+        factor: f32,
+        sampling: Sampling,
+        rng: &mut impl RngCore,
+    ) -> (Matrix, f32) {
+        self.view()
+            .rand_matmul_with_estimate(other, factor, sampling, rng)
+    }
+
+    pub fn try_rand_matmul_with_estimate<'b>(
+        &self,
+        other: impl Into<MatrixView<'b>>,
+        factor: f32,
+        sampling: Sampling,
+        rng: &mut impl RngCore,
+    ) -> Result<(Matrix, f32)> {
+        self.view()
+            .try_rand_matmul_with_estimate(other, factor, sampling, rng)
+    }
+}
+
+impl MatrixView<'_> {
+    pub fn rand_matmul_with_estimate<'b>(
+        &self,
+        other: impl Into<MatrixView<'b>>,
+        factor: f32,
+        sampling: Sampling,
+        rng: &mut impl RngCore,
+    ) -> (Matrix, f32) {
+        self.try_rand_matmul_with_estimate(other, factor, sampling, rng)
+            .or_panic()
+    }
+
+    pub fn try_rand_matmul_with_estimate<'b>(
+        &self,
+        other: impl Into<MatrixView<'b>>,
+        factor: f32,
+        sampling: Sampling,
+        rng: &mut impl RngCore,
+    ) -> Result<(Matrix, f32)> {
+        let other = other.into();
+        check_matmul(self.shape(), other.shape())?;
+        check_factor(factor)?;
+
+        let (_m, n) = self.shape();
+
+        let a_col_norm = self.col_norms(Norm::L2).data;
+        let b_row_norm = other.row_norms(Norm::L2).data;
+        let prob = probabilities(&a_col_norm, &b_row_norm);
+        let c = num_samples(n, factor);
+
+        let plan = sampling.plan(&prob, c, rng);
+        let result = outer_products(*self, other, &plan);
+
+        // squared Frobenius norm of the `k`-th outer product
+        let x2 = |k: usize| (a_col_norm[k] * b_row_norm[k]).powi(2);
+
+        let estimate = match sampling {
+            // sample variance of the `c` single-sample estimators `Y_i = A^(k)·B_(k) / p_k`
+            // (whose mean is `result`): `(Σ_i |Y_i|² - c |result|²) / (c (c - 1))`
+            Sampling::WithReplacement | Sampling::Uniform if c > 1 => {
+                let p = |k: usize| match sampling {
+                    Sampling::Uniform => 1. / n as f32,
+                    _ => prob[k],
+                };
+
+                // each entry in the plan aggregates `scale * c * p_k` draws of the same index
+                let sum_y2: f32 = plan
+                    .iter()
+                    .map(|&(k, scale)| scale * c as f32 * p(k) * x2(k) / p(k).powi(2))
+                    .sum();
+
+                let result2: f32 = result.data.iter().map(|x| x * x).sum();
+
+                let c = c as f32;
+                ((sum_y2 - c * result2) / (c * (c - 1.))).max(0.)
+            }
+            // Horvitz-Thompson estimate of `Σ_k x_k² (1 - q_k) / q_k` (with `scale = 1 / q_k`)
+            Sampling::WithoutReplacement => plan
+                .iter()
+                .map(|&(k, scale)| x2(k) * (scale - 1.) * scale)
+                .sum(),
+            // remainder of the deterministic bound (everything that wasn't evaluated)
+            Sampling::TopC => {
+                let total: f32 = (0..n).map(|k| x2(k).sqrt()).sum();
+                let kept: f32 = plan.iter().map(|&(k, _)| x2(k).sqrt()).sum();
+                (total - kept).max(0.).powi(2)
+            }
+            _ => ErrorBound::from_norms(&a_col_norm, &b_row_norm).expected_for_samples(sampling, c),
+        };
+
+        Ok((result, estimate))
+    }
+}
+
+#[cfg(test)]
+fn squared_error(expected: &Matrix, actual: &Matrix) -> f32 {
+    expected.sub(actual).data.iter().map(|x| x * x).sum()
+}
+
+#[test]
+fn bounds_hold_on_average() {
+    use rand_core::SeedableRng;
+
+    let a = Matrix::random(8, 32, 0);
+    let b = Matrix::random(32, 6, 1);
+    let expected = a.matmul(&b);
+
+    let bound = a.rand_matmul_error_bound(&b);
+    let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+
+    for sampling in [
+        Sampling::WithReplacement,
+        Sampling::WithoutReplacement,
+        Sampling::TopC,
+        Sampling::Uniform,
+    ] {
+        const N: usize = 500;
+
+        let (mut error, mut estimate) = (0., 0.);
+        for _ in 0..N {
+            let (actual, e) = a.rand_matmul_with_estimate(&b, 0.25, sampling, &mut rng);
+            error += squared_error(&expected, &actual) / N as f32;
+            estimate += e / N as f32;
+        }
+
+        let theoretical = bound.expected(sampling, 0.25);
+        assert!(
+            error <= theoretical * 1.1,
+            "{sampling:?}: {error} > {theoretical}"
+        );
+
+        // the runtime estimate tracks the theoretical value
+        let ratio = estimate / theoretical;
+        assert!(
+            (0.5..=1.5).contains(&ratio),
+            "{sampling:?}: {estimate} vs {theoretical}"
+        );
+
+        assert!(bound.confidence(sampling, 0.25, 0.1) >= theoretical.sqrt());
+    }
+}
+
+#[test]
+fn samples_for_target() {
+    let a = Matrix::random(8, 64, 0);
+    let b = Matrix::random(64, 6, 1);
+
+    let bound = a.rand_matmul_error_bound(&b);
+
+    for sampling in [Sampling::WithReplacement, Sampling::WithoutReplacement] {
+        let target = bound.expected_for_samples(sampling, 16);
+
+        let c = bound.samples_for(sampling, target).unwrap();
+        assert!(c <= 16);
+        assert!(bound.expected_for_samples(sampling, c) <= target);
+        assert!(bound.expected_for_samples(sampling, c - 1) > target);
+
+        assert_eq!(bound.factor_for(sampling, target), Some(c as f32 / 64.));
+    }
+
+    // exact evaluation is only guaranteed when sampling without replacement
+    assert_eq!(
+        bound.samples_for(Sampling::WithoutReplacement, 0.),
+        Some(64)
+    );
+    assert_eq!(bound.samples_for(Sampling::WithReplacement, 0.), None);
+
+    // without samples the whole product is dropped, so `c = 0` never meets a smaller target
+    let bound = ErrorBound::from_norms(&[1., 1.], &[1., 1.]);
+    for sampling in [
+        Sampling::WithReplacement,
+        Sampling::WithoutReplacement,
+        Sampling::TopC,
+        Sampling::Uniform,
+    ] {
+        assert_eq!(bound.expected_for_samples(sampling, 0), 4.);
+        assert_ne!(bound.samples_for(sampling, 2.), Some(0), "{sampling:?}");
+    }
+}
@@ -0,0 +1,98 @@
+//! Weight initializers.
+//! * 'Understanding the difficulty of training deep feedforward neural networks' (Glorot, Bengio)
+//!   - Xavier
+//! * 'Delving Deep into Rectifiers' (He, Zhang, Ren, Sun) - He
+//!
+//! The weights of a dense layer `x·W` have shape `(fan_in, fan_out)`.
+
+use super::Matrix;
+use crate::error::{Error, OrPanic, Result};
+use rand_core::RngCore;
+use rand_distr::{Normal, Uniform};
+
+impl Matrix {
+    /// I.i.d. `N(mean, std²)`.
+    pub fn normal(m: usize, n: usize, mean: f32, std: f32, rng: &mut impl RngCore) -> Self {
+        Self::try_normal(m, n, mean, std, rng).or_panic()
+    }
+
+    pub fn try_normal(
+        m: usize,
+        n: usize,
+        mean: f32,
+        std: f32,
+        rng: &mut impl RngCore,
+    ) -> Result<Self> {
+        if !mean.is_finite() || !std.is_finite() || std < 0. {
+            return Err(Error::InvalidArgument(format!(
+                "normal distribution with mean {mean} and standard deviation {std}"
+            )));
+        }
+
+        let dist = Normal::new(mean, std).map_err(|e| Error::InvalidArgument(e.to_string()))?;
+        Ok(Self::random_with(m, n, rng, dist))
+    }
+
+    /// `U(-a, a)` with `a = sqrt(6 / (fan_in + fan_out))`.
+    pub fn xavier_uniform(fan_in: usize, fan_out: usize, rng: &mut impl RngCore) -> Self {
+        let a = (6. / (fan_in + fan_out).max(1) as f32).sqrt();
+        Self::random_with(fan_in, fan_out, rng, Uniform::new_inclusive(-a, a))
+    }
+
+    /// `N(0, 2 / (fan_in + fan_out))`.
+    pub fn xavier_normal(fan_in: usize, fan_out: usize, rng: &mut impl RngCore) -> Self {
+        let std = (2. / (fan_in + fan_out).max(1) as f32).sqrt();
+        Self::normal(fan_in, fan_out, 0., std, rng)
+    }
+
+    /// `U(-a, a)` with `a = sqrt(6 / fan_in)`, for ReLU layers.
+    pub fn he_uniform(fan_in: usize, fan_out: usize, rng: &mut impl RngCore) -> Self {
+        let a = (6. / fan_in.max(1) as f32).sqrt();
+        Self::random_with(fan_in, fan_out, rng, Uniform::new_inclusive(-a, a))
+    }
+
+    /// `N(0, 2 / fan_in)`, for ReLU layers.
+    pub fn he_normal(fan_in: usize, fan_out: usize, rng: &mut impl RngCore) -> Self {
+        let std = (2. / fan_in.max(1) as f32).sqrt();
+        Self::normal(fan_in, fan_out, 0., std, rng)
+    }
+}
+
+#[test]
+fn initializers() {
+    use rand_core::SeedableRng;
+
+    let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+
+    let variance = |w: &Matrix| {
+        let n = w.data.len() as f32;
+        let mean = w.data.iter().sum::<f32>() / n;
+        w.data.iter().map(|x| (x - mean) * (x - mean)).sum::<f32>() / n
+    };
+
+    // all of them have variance `2 / fan_in` (He) or `2 / (fan_in + fan_out)` (Xavier)
+    let (fan_in, fan_out) = (200, 300);
+    for (w, expected) in [
+        (Matrix::xavier_uniform(fan_in, fan_out, &mut rng), 2. / 500.),
+        (Matrix::xavier_normal(fan_in, fan_out, &mut rng), 2. / 500.),
+        (Matrix::he_uniform(fan_in, fan_out, &mut rng), 2. / 200.),
+        (Matrix::he_normal(fan_in, fan_out, &mut rng), 2. / 200.),
+    ] {
+        assert_eq!(w.shape(), (fan_in, fan_out));
+        let ratio = variance(&w) / expected;
+        assert!((ratio - 1.).abs() < 0.05, "{ratio}");
+    }
+
+    let w = Matrix::normal(100, 100, 3., 0.5, &mut rng);
+    let mean = w.data.iter().sum::<f32>() / 1e4;
+    assert!((mean - 3.).abs() < 0.05);
+    assert!((variance(&w).sqrt() - 0.5).abs() < 0.05);
+
+    assert!(Matrix::try_normal(2, 2, 0., -1., &mut rng).is_err());
+    assert!(Matrix::try_normal(2, 2, 0., f32::NAN, &mut rng).is_err());
+    assert!(Matrix::try_normal(2, 2, f32::INFINITY, 1., &mut rng).is_err());
+    assert_eq!(
+        Matrix::try_normal(2, 2, 1., 0., &mut rng).unwrap(),
+        Matrix::fill(2, 2, 1.)
+    );
+}
@@ -0,0 +1,408 @@
+//! Randomized low-rank approximation (truncated SVD) of weight matrices.
+//! * 'Finding Structure with Randomness: Probabilistic Algorithms for Constructing Approximate
+//!   Matrix Decompositions' (Halko, Martinsson, Tropp)
+//!
+//! A `(m, n)` matrix `W` is approximated by `U·V`, with `U` of shape `(m, k)` and `V` of shape
+//! `(k, n)`, so that `x·W` costs `O(k (m + n))` instead of `O(m n)` per input row.
+
+use super::{Matrix, MatrixView};
+use crate::error::{check_matmul, Error, OrPanic, Result};
+use rand_core::RngCore;
+
+/// Truncated singular value decomposition `W ≈ U·diag(s)·Vt` (singular values in descending
+/// order).
+#[derive(Clone, Debug)]
+pub struct Svd {
+    pub u: Matrix,
+    pub s: Box<[f32]>,
+    pub vt: Matrix,
+}
+
+/// Parameters of the randomized range finder.
+#[derive(Clone, Copy, Debug)]
+pub struct SvdOptions {
+    /// Number of extra random samples beyond the target rank.
+    pub oversampling: usize,
+    /// Number of power iterations (improves accuracy for slowly decaying spectra).
+    pub power_iterations: usize,
+}
+
+impl Default for SvdOptions {
+    fn default() -> Self {
+        Self {
+            oversampling: 10,
+            power_iterations: 2,
+        }
+    }
+}
+
+/// Target rank of a low-rank approximation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rank {
+    Fixed(usize),
+    /// Smallest rank that retains the given fraction (`0..=1`) of the squared Frobenius norm.
+    Energy(f32),
+}
+
+/// Factored matrix `W ≈ U·V`.
+#[derive(Clone, Debug)]
+pub struct LowRank {
+    u: Matrix,
+    v: Matrix,
+}
+
+// modified Gram-Schmidt (applied twice for stability), zeroing columns that are (numerically)
+// linearly dependent on the previous ones
+fn orthonormalize(y: &mut Matrix) {
+    let (m, l) = y.shape();
+
+    for j in 0..l {
+        let norm0 = (0..m).map(|i| y[(i, j)].powi(2)).sum::<f32>().sqrt();
+
+        for _ in 0..2 {
+            for k in 0..j {
+                let dot: f32 = (0..m).map(|i| y[(i, k)] * y[(i, j)]).sum();
+                for i in 0..m {
+                    y[(i, j)] -= dot * y[(i, k)];
+                }
+            }
+        }
+
+        let norm = (0..m).map(|i| y[(i, j)].powi(2)).sum::<f32>().sqrt();
+        let scale = if norm > 1e-5 * norm0.max(f32::MIN_POSITIVE) {
+            1. / norm
+        } else {
+            0.
+        };
+
+        for i in 0..m {
+            y[(i, j)] *= scale;
+        }
+    }
+}
+
+// one-sided Jacobi (Hestenes) SVD of `b` (shape `(l, n)`): returns `(U, s, V)` with
+// `b = U·diag(s)·Vᵀ`, `U` of shape `(l, l)` and `V` of shape `(n, l)`
+fn jacobi_svd(b: MatrixView) -> (Matrix, Box<[f32]>, Matrix) {
+    const SWEEPS: usize = 30;
+    const EPSILON: f32 = 1e-7;
+
+    let (l, n) = b.shape();
+
+    // orthogonalize the columns of `G = bᵀ`, accumulating the rotations in `J`
+    let mut g = b.transpose().to_matrix();
+    let mut j = Matrix::zeroes(l, l);
+    for i in 0..l {
+        j[(i, i)] = 1.;
+    }
+
+    for _ in 0..SWEEPS {
+        let mut rotated = false;
+
+        for p in 0..l {
+            for q in p + 1..l {
+                let (mut alpha, mut beta, mut gamma) = (0., 0., 0.);
+                for i in 0..n {
+                    alpha += g[(i, p)] * g[(i, p)];
+                    beta += g[(i, q)] * g[(i, q)];
+                    gamma += g[(i, p)] * g[(i, q)];
+                }
+
+                if gamma.abs() <= EPSILON * (alpha * beta).sqrt() || gamma == 0. {
+                    continue;
+                }
+                rotated = true;
+
+                let zeta = (beta - alpha) / (2. * gamma);
+                let t = zeta.signum() / (zeta.abs() + (1. + zeta * zeta).sqrt());
+                let c = 1. / (1. + t * t).sqrt();
+                let s = c * t;
+
+                let rotate = |m: &mut Matrix, rows: usize| {
+                    for i in 0..rows {
+                        let (x, y) = (m[(i, p)], m[(i, q)]);
+                        m[(i, p)] = c * x - s * y;
+                        m[(i, q)] = s * x + c * y;
+                    }
+                };
+                rotate(&mut g, n);
+                rotate(&mut j, l);
+            }
+        }
+
+        if !rotated {
+            break;
+        }
+    }
+
+    // G·J = V·diag(s)  =>  b = J·diag(s)·Vᵀ
+    let s: Box<[f32]> = (0..l)
+        .map(|p| (0..n).map(|i| g[(i, p)].powi(2)).sum::<f32>().sqrt())
+        .collect();
+
+    for p in 0..l {
+        let scale = if s[p] > 0. { 1. / s[p] } else { 0. };
+        for i in 0..n {
+            g[(i, p)] *= scale;
+        }
+    }
+
+    (j, s, g)
+}
+
+impl MatrixView<'_> {
+    pub fn randomized_svd(&self, rank: usize, options: SvdOptions, rng: &mut impl RngCore) -> Svd {
+        let (m, n) = self.shape();
+
+        let k = rank.min(m.min(n));
+        let l = (k + options.oversampling).min(m.min(n));
+
+        // range finder: orthonormal basis `Q` for the range of `W·Ω`
+        let mut y = self.matmul(&Matrix::random_with(n, l, rng, rand_distr::StandardNormal));
+        orthonormalize(&mut y);
+
+        for _ in 0..options.power_iterations {
+            let mut z = self.transpose().matmul(&y);
+            orthonormalize(&mut z);
+
+            y = self.matmul(&z);
+            orthonormalize(&mut y);
+        }
+
+        // W ≈ Q·(Qᵀ·W), where the SVD of the small `(l, n)` matrix `Qᵀ·W` is computed exactly
+        let b = y.transpose().matmul(*self);
+        let (ub, s, v) = jacobi_svd(b.view());
+        let u = y.matmul(&ub);
+
+        let mut order: Vec<usize> = (0..l).collect();
+        order.sort_unstable_by(|&i, &j| s[j].total_cmp(&s[i]));
+        order.truncate(k);
+
+        let mut svd = Svd {
+            u: Matrix::zeroes(m, k),
+            s: order.iter().map(|&i| s[i]).collect(),
+            vt: Matrix::zeroes(k, n),
+        };
+
+        for (r, &i) in order.iter().enumerate() {
+            for x in 0..m {
+                svd.u[(x, r)] = u[(x, i)];
+            }
+            for x in 0..n {
+                svd.vt[(r, x)] = v[(x, i)];
+            }
+        }
+
+        svd
+    }
+
+    pub fn low_rank(&self, rank: Rank, options: SvdOptions, rng: &mut impl RngCore) -> LowRank {
+        self.try_low_rank(rank, options, rng).or_panic()
+    }
+
+    pub fn try_low_rank(
+        &self,
+        rank: Rank,
+        options: SvdOptions,
+        rng: &mut impl RngCore,
+    ) -> Result<LowRank> {
+        let (m, n) = self.shape();
+
+        let svd = match rank {
+            Rank::Fixed(k) => self.randomized_svd(k, options, rng),
+            Rank::Energy(fraction) => {
+                if !(0. ..=1.).contains(&fraction) {
+                    return Err(Error::InvalidArgument(format!(
+                        "energy fraction {fraction} (not in [0, 1])"
+                    )));
+                }
+
+                let total: f32 = (0..m)
+                    .flat_map(|i| (0..n).map(move |j| (i, j)))
+                    .map(|ij| self[ij].powi(2))
+                    .sum();
+
+                // double the rank until enough energy is captured
+                let mut k = 8.min(m.min(n));
+                loop {
+                    let mut svd = self.randomized_svd(k, options, rng);
+
+                    let (mut energy, mut kept) = (0., 0);
+                    for &s in svd.s.iter() {
+                        if energy >= fraction * total {
+                            break;
+                        }
+                        energy += s * s;
+                        kept += 1;
+                    }
+
+                    if energy >= fraction * total || k == m.min(n) {
+                        svd.truncate(kept);
+                        break svd;
+                    }
+
+                    k = (2 * k).min(m.min(n));
+                }
+            }
+        };
+
+        Ok(LowRank::from(svd))
+    }
+}
+
+impl Matrix {
+    /// Truncated SVD of rank `min(rank, m, n)`. Every rank and shape is valid (ranks above
+    /// `min(m, n)` are clamped), so unlike `low_rank` there is no `try_` variant.
+    pub fn randomized_svd(&self, rank: usize, options: SvdOptions, rng: &mut impl RngCore) -> Svd {
+        self.view().randomized_svd(rank, options, rng)
+    }
+
+    pub fn low_rank(&self, rank: Rank, options: SvdOptions, rng: &mut impl RngCore) -> LowRank {
+        self.view().low_rank(rank, options, rng)
+    }
+
+    pub fn try_low_rank(
+        &self,
+        rank: Rank,
+        options: SvdOptions,
+        rng: &mut impl RngCore,
+    ) -> Result<LowRank> {
+        self.view().try_low_rank(rank, options, rng)
+    }
+
+    /// `self·W` for a factored `W`, evaluated as two thin products.
+    pub fn low_rank_matmul(&self, other: &LowRank) -> Matrix {
+        self.try_low_rank_matmul(other).or_panic()
+    }
+
+    pub fn try_low_rank_matmul(&self, other: &LowRank) -> Result<Matrix> {
+        self.try_matmul(&other.u)?.try_matmul(&other.v)
+    }
+}
+
+impl Svd {
+    pub fn rank(&self) -> usize {
+        self.s.len()
+    }
+
+    pub fn truncate(&mut self, rank: usize) {
+        let rank = rank.min(self.rank());
+
+        self.u = self.u.slice(.., ..rank).to_matrix();
+        self.s = self.s[..rank].into();
+        self.vt = self.vt.slice(..rank, ..).to_matrix();
+    }
+}
+
+impl From<Svd> for LowRank {
+    fn from(svd: Svd) -> Self {
+        let Svd { mut u, s, vt } = svd;
+
+        let (m, k) = u.shape();
+        for i in 0..m {
+            for j in 0..k {
+                u[(i, j)] *= s[j];
+            }
+        }
+
+        Self { u, v: vt }
+    }
+}
+
+impl LowRank {
+    pub fn new(u: Matrix, v: Matrix) -> Self {
+        Self::try_new(u, v).or_panic()
+    }
+
+    pub fn try_new(u: Matrix, v: Matrix) -> Result<Self> {
+        check_matmul(u.shape(), v.shape())?;
+        Ok(Self { u, v })
+    }
+
+    pub fn shape(&self) -> (usize, usize) {
+        (self.u.shape().0, self.v.shape().1)
+    }
+
+    pub fn rank(&self) -> usize {
+        self.u.shape().1
+    }
+
+    pub fn factors(&self) -> (&Matrix, &Matrix) {
+        (&self.u, &self.v)
+    }
+
+    pub fn to_matrix(&self) -> Matrix {
+        self.u.matmul(&self.v)
+    }
+
+    /// `W·other`, evaluated as two thin products.
+    pub fn matmul<'b>(&self, other: impl Into<MatrixView<'b>>) -> Matrix {
+        self.try_matmul(other).or_panic()
+    }
+
+    pub fn try_matmul<'b>(&self, other: impl Into<MatrixView<'b>>) -> Result<Matrix> {
+        self.u.try_matmul(&self.v.try_matmul(other)?)
+    }
+}
+
+#[test]
+fn exact_low_rank() {
+    use rand_core::SeedableRng;
+
+    let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+
+    // rank 3 by construction
+    let w = Matrix::random(20, 3, 0).matmul(&Matrix::random(3, 15, 1));
+
+    let svd = w.randomized_svd(3, SvdOptions::default(), &mut rng);
+    assert_eq!(svd.rank(), 3);
+    assert!(svd.s.windows(2).all(|s| s[0] >= s[1]));
+
+    // orthonormal factors
+    let identity = svd.u.transpose().matmul(&svd.u);
+    for i in 0..3 {
+        for j in 0..3 {
+            let expected = if i == j { 1. } else { 0. };
+            assert!((identity[(i, j)] - expected).abs() < 1e-4);
+        }
+    }
+
+    let lr = LowRank::from(svd);
+    assert!(lr.to_matrix().max_abs_error(&w) < 1e-4);
+
+    let x = Matrix::random(4, 20, 2);
+    assert!(x.low_rank_matmul(&lr).max_abs_error(&x.matmul(&w)) < 1e-4);
+
+    let y = Matrix::random(15, 2, 3);
+    assert!(lr.matmul(&y).max_abs_error(&w.matmul(&y)) < 1e-4);
+
+    // the remaining energy is (numerically) zero
+    let lr = w.low_rank(Rank::Energy(0.9999), SvdOptions::default(), &mut rng);
+    assert!(lr.rank() <= 3);
+}
+
+#[test]
+fn singular_values() {
+    use rand_core::SeedableRng;
+
+    let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+
+    let w = Matrix::new(&[
+        [0., 0., 3., 0.],
+        [0., 0., 0., 0.],
+        [5., 0., 0., 0.],
+        [0., 0., 0., 1.],
+        [0., 2., 0., 0.],
+    ]);
+
+    let svd = w.randomized_svd(4, SvdOptions::default(), &mut rng);
+    for (s, expected) in svd.s.iter().zip([5., 3., 2., 1.]) {
+        assert!((s - expected).abs() < 1e-5, "{:?}", svd.s);
+    }
+
+    // 5² + 3² = 34 of 39
+    let lr = w.low_rank(Rank::Energy(0.8), SvdOptions::default(), &mut rng);
+    assert_eq!(lr.rank(), 2);
+    let lr = w.low_rank(Rank::Energy(0.9), SvdOptions::default(), &mut rng);
+    assert_eq!(lr.rank(), 3);
+}
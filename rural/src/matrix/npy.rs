@@ -0,0 +1,534 @@
+//! NumPy `.npy` and `.npz` files.
+//! * https://numpy.org/doc/stable/reference/generated/numpy.lib.format.html
+//!
+//! Reads `float32`/`float64` arrays (either byte order, C- or Fortran-order) with up to two
+//! dimensions: scalars are read as `(1, 1)` and vectors as `(1, n)` row vectors. Writes
+//! little-endian `float32` (by default) or `float64`, in C- (by default) or Fortran-order.
+//!
+//! An `.npz` is a zip archive of `.npy` files. Only stored (`np.savez`) archives are supported,
+//! not deflated ones (`np.savez_compressed`).
+
+use super::Matrix;
+use crate::error::{Error, Result};
+use std::io::{Read, Write};
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+const LOCAL_HEADER: u32 = 0x04034b50;
+const CENTRAL_HEADER: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+
+// 1980-01-01, the earliest MS-DOS date
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+fn format_error(message: impl Into<String>) -> Error {
+    Error::Format(message.into())
+}
+
+/// Element type of a written `.npy` file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dtype {
+    /// `<f4`
+    #[default]
+    F32,
+    /// `<f8`
+    F64,
+}
+
+/// Memory layout of a written `.npy` file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Order {
+    /// Row-major.
+    #[default]
+    C,
+    /// Column-major.
+    Fortran,
+}
+
+// the value of `key` in the header dict (e.g. `'shape': (2, 3), ...` for `shape`)
+fn field<'h>(header: &'h str, key: &str) -> Result<&'h str> {
+    let quoted = format!("'{key}'");
+    let start = header
+        .find(&quoted)
+        .ok_or_else(|| format_error(format!("missing '{key}' in .npy header")))?;
+
+    header[start + quoted.len()..]
+        .trim_start()
+        .strip_prefix(':')
+        .map(str::trim_start)
+        .ok_or_else(|| format_error(format!("malformed '{key}' in .npy header")))
+}
+
+struct Header {
+    little_endian: bool,
+    // bytes per element
+    size: usize,
+    fortran_order: bool,
+    shape: (usize, usize),
+}
+
+impl Header {
+    fn parse(header: &str) -> Result<Self> {
+        let descr = field(header, "descr")?
+            .strip_prefix('\'')
+            .and_then(|descr| descr.split('\'').next())
+            .ok_or_else(|| format_error("malformed 'descr' in .npy header"))?;
+
+        let (little_endian, size) = match descr.as_bytes() {
+            [order @ (b'<' | b'>' | b'='), b'f', size @ (b'4' | b'8')] => {
+                let little_endian = match order {
+                    b'<' => true,
+                    b'>' => false,
+                    _ => cfg!(target_endian = "little"),
+                };
+                (little_endian, (size - b'0') as usize)
+            }
+            _ => return Err(Error::Unsupported(format!("dtype '{descr}'"))),
+        };
+
+        let fortran_order = match field(header, "fortran_order")? {
+            order if order.starts_with("True") => true,
+            order if order.starts_with("False") => false,
+            _ => return Err(format_error("malformed 'fortran_order' in .npy header")),
+        };
+
+        let dims = field(header, "shape")?
+            .strip_prefix('(')
+            .and_then(|shape| shape.split(')').next())
+            .ok_or_else(|| format_error("malformed 'shape' in .npy header"))?
+            .split(',')
+            .map(str::trim)
+            .filter(|dim| !dim.is_empty())
+            .map(|dim| {
+                dim.parse::<usize>()
+                    .map_err(|_| format_error(format!("invalid dimension '{dim}'")))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let shape = match dims[..] {
+            [] => (1, 1),
+            [n] => (1, n),
+            [m, n] => (m, n),
+            _ => {
+                return Err(Error::Unsupported(format!(
+                    "{}-dimensional arrays",
+                    dims.len()
+                )))
+            }
+        };
+
+        Ok(Self {
+            little_endian,
+            size,
+            fortran_order,
+            shape,
+        })
+    }
+}
+
+fn read_u16(reader: &mut impl Read) -> Result<u16> {
+    let mut bytes = [0; 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_bytes(reader: &mut impl Read, len: usize) -> Result<Vec<u8>> {
+    // NOTE: not `vec![0; len]`, which would trust the length in a (possibly truncated) file
+    let mut bytes = Vec::new();
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+
+    if bytes.len() == len {
+        Ok(bytes)
+    } else {
+        Err(format_error("unexpected end of file"))
+    }
+}
+
+// CRC-32 (IEEE), as used by zip
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb88320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+impl Matrix {
+    /// Reads a `.npy` file.
+    pub fn read_npy(mut reader: impl Read) -> Result<Matrix> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic[..6] != MAGIC {
+            return Err(format_error("not a .npy file"));
+        }
+
+        let header_len = match magic[6] {
+            1 => read_u16(&mut reader)? as usize,
+            2 | 3 => read_u32(&mut reader)? as usize,
+            major => return Err(Error::Unsupported(format!(".npy version {major}"))),
+        };
+        let header = read_bytes(&mut reader, header_len)?;
+        let header = Header::parse(
+            core::str::from_utf8(&header).map_err(|_| format_error("invalid .npy header"))?,
+        )?;
+
+        let (m, n) = header.shape;
+        let len = m
+            .checked_mul(n)
+            .and_then(|len| len.checked_mul(header.size))
+            .ok_or_else(|| format_error("array too large"))?;
+        let bytes = read_bytes(&mut reader, len)?;
+
+        let values: Vec<f32> = match (header.size, header.little_endian) {
+            (4, true) => bytes
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+                .collect(),
+            (4, false) => bytes
+                .chunks_exact(4)
+                .map(|b| f32::from_be_bytes(b.try_into().unwrap()))
+                .collect(),
+            (_, true) => bytes
+                .chunks_exact(8)
+                .map(|b| f64::from_le_bytes(b.try_into().unwrap()) as f32)
+                .collect(),
+            (_, false) => bytes
+                .chunks_exact(8)
+                .map(|b| f64::from_be_bytes(b.try_into().unwrap()) as f32)
+                .collect(),
+        };
+
+        if header.fortran_order {
+            Ok(Matrix::from_fn(m, n, |i, j| values[j * m + i]))
+        } else {
+            Matrix::try_from_vec((m, n), values)
+        }
+    }
+
+    /// Writes a (version 1.0) `.npy` file, as `float32` in C-order.
+    pub fn write_npy(&self, writer: impl Write) -> Result<()> {
+        self.write_npy_with(writer, Dtype::F32, Order::C)
+    }
+
+    /// Writes a (version 1.0) `.npy` file with the given element type and layout.
+    pub fn write_npy_with(&self, mut writer: impl Write, dtype: Dtype, order: Order) -> Result<()> {
+        let (m, n) = self.shape;
+
+        let descr = match dtype {
+            Dtype::F32 => "<f4",
+            Dtype::F64 => "<f8",
+        };
+        let fortran_order = match order {
+            Order::C => "False",
+            Order::Fortran => "True",
+        };
+
+        let mut header = format!(
+            "{{'descr': '{descr}', 'fortran_order': {fortran_order}, 'shape': ({m}, {n}), }}"
+        );
+        // the data starts at a multiple of 64 bytes, and the header ends with a newline
+        let len = MAGIC.len() + 4 + header.len() + 1;
+        header.push_str(&" ".repeat(len.next_multiple_of(64) - len));
+        header.push('\n');
+
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[1, 0])?;
+        writer.write_all(&(header.len() as u16).to_le_bytes())?;
+        writer.write_all(header.as_bytes())?;
+
+        let values: Box<dyn Iterator<Item = f32>> = match order {
+            Order::C => Box::new(self.data.iter().copied()),
+            Order::Fortran => Box::new((0..n).flat_map(|j| (0..m).map(move |i| self[(i, j)]))),
+        };
+        let bytes: Vec<u8> = match dtype {
+            Dtype::F32 => values.flat_map(|x| x.to_le_bytes()).collect(),
+            Dtype::F64 => values.flat_map(|x| (x as f64).to_le_bytes()).collect(),
+        };
+        writer.write_all(&bytes)?;
+
+        Ok(())
+    }
+
+    /// Reads all arrays of a `.npz` file, in order, with their names (without `.npy`).
+    pub fn read_npz(mut reader: impl Read) -> Result<Vec<(String, Matrix)>> {
+        let mut arrays = vec![];
+
+        loop {
+            match read_u32(&mut reader)? {
+                LOCAL_HEADER => {}
+                // the central directory repeats the local headers
+                CENTRAL_HEADER | END_OF_CENTRAL_DIRECTORY => break,
+                _ => return Err(format_error("not a zip archive")),
+            }
+
+            let _version = read_u16(&mut reader)?;
+            let flags = read_u16(&mut reader)?;
+            let method = read_u16(&mut reader)?;
+            let _time = read_u16(&mut reader)?;
+            let _date = read_u16(&mut reader)?;
+            let crc = read_u32(&mut reader)?;
+            let mut compressed_size = read_u32(&mut reader)? as u64;
+            let mut size = read_u32(&mut reader)? as u64;
+            let name_len = read_u16(&mut reader)? as usize;
+            let extra_len = read_u16(&mut reader)? as usize;
+
+            let name = read_bytes(&mut reader, name_len)?;
+            let name = String::from_utf8(name).map_err(|_| format_error("invalid name"))?;
+            let extra = read_bytes(&mut reader, extra_len)?;
+
+            if flags & 1 != 0 {
+                return Err(Error::Unsupported(format!("encrypted member '{name}'")));
+            }
+            if flags & 8 != 0 {
+                return Err(Error::Unsupported(format!(
+                    "member '{name}' without sizes in its local header"
+                )));
+            }
+            if method != 0 {
+                return Err(Error::Unsupported(format!(
+                    "compressed member '{name}' (only `np.savez` archives are supported)"
+                )));
+            }
+
+            // `np.savez` forces zip64, which moves the sizes into an extra field
+            let mut fields = &extra[..];
+            while fields.len() >= 4 {
+                let id = u16::from_le_bytes([fields[0], fields[1]]);
+                let len = u16::from_le_bytes([fields[2], fields[3]]) as usize;
+                let data = fields
+                    .get(4..4 + len)
+                    .ok_or_else(|| format_error("malformed extra field"))?;
+
+                if id == 1 {
+                    let mut values = data
+                        .chunks_exact(8)
+                        .map(|b| u64::from_le_bytes(b.try_into().unwrap()));
+                    if size == u32::MAX as u64 {
+                        size = values
+                            .next()
+                            .ok_or_else(|| format_error("missing zip64 size"))?;
+                    }
+                    if compressed_size == u32::MAX as u64 {
+                        compressed_size = values
+                            .next()
+                            .ok_or_else(|| format_error("missing zip64 size"))?;
+                    }
+                }
+                fields = &fields[4 + len..];
+            }
+            if size != compressed_size {
+                return Err(format_error(format!("inconsistent sizes of '{name}'")));
+            }
+
+            let data = read_bytes(&mut reader, size as usize)?;
+            if crc32(&data) != crc {
+                return Err(format_error(format!("CRC mismatch in '{name}'")));
+            }
+
+            let name = name.strip_suffix(".npy").unwrap_or(&name).to_string();
+            arrays.push((name, Matrix::read_npy(&data[..])?));
+        }
+
+        Ok(arrays)
+    }
+
+    /// Writes a stored (uncompressed) `.npz` file, like `np.savez`.
+    pub fn write_npz<'a>(
+        mut writer: impl Write,
+        arrays: impl IntoIterator<Item = (&'a str, &'a Matrix)>,
+    ) -> Result<()> {
+        let too_large = || Error::Unsupported("archives larger than 4 GiB".to_string());
+
+        let mut central_directory = vec![];
+        let mut offset = 0u32;
+        let mut count = 0u16;
+
+        for (name, matrix) in arrays {
+            let name = format!("{name}.npy");
+            let mut data = vec![];
+            matrix.write_npy(&mut data)?;
+
+            let crc = crc32(&data);
+            let size: u32 = data.len().try_into().map_err(|_| too_large())?;
+
+            // shared by the local and the central header, after their version fields
+            let mut fields = vec![];
+            fields.extend(20u16.to_le_bytes()); // version needed to extract (2.0)
+            fields.extend(0u16.to_le_bytes()); // flags
+            fields.extend(0u16.to_le_bytes()); // method (stored)
+            fields.extend(0u16.to_le_bytes()); // time
+            fields.extend(DOS_DATE.to_le_bytes());
+            fields.extend(crc.to_le_bytes());
+            fields.extend(size.to_le_bytes()); // compressed
+            fields.extend(size.to_le_bytes());
+            fields.extend((name.len() as u16).to_le_bytes());
+            fields.extend(0u16.to_le_bytes()); // extra field length
+
+            let mut local = vec![];
+            local.extend(LOCAL_HEADER.to_le_bytes());
+            local.extend(&fields);
+            local.extend(name.as_bytes());
+
+            writer.write_all(&local)?;
+            writer.write_all(&data)?;
+
+            central_directory.extend(CENTRAL_HEADER.to_le_bytes());
+            central_directory.extend(20u16.to_le_bytes()); // version made by
+            central_directory.extend(&fields);
+            central_directory.extend(0u16.to_le_bytes()); // comment length
+            central_directory.extend(0u16.to_le_bytes()); // disk number
+            central_directory.extend(0u16.to_le_bytes()); // internal attributes
+            central_directory.extend(0u32.to_le_bytes()); // external attributes
+            central_directory.extend(offset.to_le_bytes());
+            central_directory.extend(name.as_bytes());
+
+            offset = (local.len() + data.len())
+                .try_into()
+                .ok()
+                .and_then(|len| offset.checked_add(len))
+                .ok_or_else(too_large)?;
+            count = count
+                .checked_add(1)
+                .ok_or_else(|| Error::Unsupported("more than 65535 arrays".to_string()))?;
+        }
+
+        let mut end = vec![];
+        end.extend(END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+        end.extend(0u16.to_le_bytes()); // disk number
+        end.extend(0u16.to_le_bytes()); // disk with the central directory
+        end.extend(count.to_le_bytes()); // entries on this disk
+        end.extend(count.to_le_bytes());
+        end.extend((central_directory.len() as u32).to_le_bytes());
+        end.extend(offset.to_le_bytes());
+        end.extend(0u16.to_le_bytes()); // comment length
+
+        writer.write_all(&central_directory)?;
+        writer.write_all(&end)?;
+
+        Ok(())
+    }
+}
+
+#[test]
+fn npy() {
+    let a = Matrix::new(&[[1., 2., 3.], [4., 5., 6.]]);
+
+    let mut bytes = vec![];
+    a.write_npy(&mut bytes).unwrap();
+    assert_eq!(&bytes[..10], b"\x93NUMPY\x01\x00\x76\x00");
+    assert_eq!(bytes.len(), 128 + 6 * 4);
+    assert_eq!(bytes[127], b'\n');
+    assert_eq!(Matrix::read_npy(&bytes[..]).unwrap(), a);
+
+    // `np.save(f, np.array([[1., 2., 3.], [4., 5., 6.]], order='F'))`: big-endian float64
+    let header = "{'descr': '>f8', 'fortran_order': True, 'shape': (2, 3), }";
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend((header.len() as u16).to_le_bytes());
+    bytes.extend(header.as_bytes());
+    for x in [1f64, 4., 2., 5., 3., 6.] {
+        bytes.extend(x.to_be_bytes());
+    }
+    assert_eq!(Matrix::read_npy(&bytes[..]).unwrap(), a);
+
+    for dtype in [Dtype::F32, Dtype::F64] {
+        for order in [Order::C, Order::Fortran] {
+            let mut bytes = vec![];
+            a.write_npy_with(&mut bytes, dtype, order).unwrap();
+            assert_eq!(
+                Matrix::read_npy(&bytes[..]).unwrap(),
+                a,
+                "{dtype:?} {order:?}"
+            );
+        }
+    }
+    let mut bytes = vec![];
+    a.write_npy_with(&mut bytes, Dtype::F64, Order::Fortran)
+        .unwrap();
+    assert_eq!(&bytes[128..136], &1f64.to_le_bytes());
+    assert_eq!(&bytes[136..144], &4f64.to_le_bytes());
+
+    // vectors are rows
+    let header = "{'descr': '<f4', 'fortran_order': False, 'shape': (3,), }";
+    let mut bytes = b"\x93NUMPY\x02\x00".to_vec();
+    bytes.extend((header.len() as u32).to_le_bytes());
+    bytes.extend(header.as_bytes());
+    for x in [1f32, 2., 3.] {
+        bytes.extend(x.to_le_bytes());
+    }
+    assert_eq!(
+        Matrix::read_npy(&bytes[..]).unwrap(),
+        Matrix::new(&[[1., 2., 3.]])
+    );
+
+    // I/O errors keep their source
+    let error = Matrix::read_npy(&b"\x93NUMPY"[..]).unwrap_err();
+    assert!(matches!(error, Error::Io(_)));
+    assert!(std::error::Error::source(&error).is_some());
+
+    // truncated data
+    assert!(matches!(
+        Matrix::read_npy(&bytes[..bytes.len() - 1]),
+        Err(Error::Format(_))
+    ));
+
+    let header = "{'descr': '<i8', 'fortran_order': False, 'shape': (3,), }";
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend((header.len() as u16).to_le_bytes());
+    bytes.extend(header.as_bytes());
+    assert!(matches!(
+        Matrix::read_npy(&bytes[..]),
+        Err(Error::Unsupported(_))
+    ));
+}
+
+#[test]
+fn npz() {
+    assert_eq!(crc32(b"123456789"), 0xcbf43926);
+
+    let a = Matrix::random(3, 4, 0);
+    let b = Matrix::random(1, 5, 1);
+
+    let mut bytes = vec![];
+    Matrix::write_npz(&mut bytes, [("weights", &a), ("bias", &b)]).unwrap();
+
+    let arrays = Matrix::read_npz(&bytes[..]).unwrap();
+    assert_eq!(arrays.len(), 2);
+    assert_eq!(arrays[0], ("weights".to_string(), a.clone()));
+    assert_eq!(arrays[1], ("bias".to_string(), b));
+
+    // a zip64 local header, as written by `np.savez`
+    let mut data = vec![];
+    a.write_npy(&mut data).unwrap();
+    let mut zip64 = vec![];
+    zip64.extend(LOCAL_HEADER.to_le_bytes());
+    zip64.extend([45, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    zip64.extend(crc32(&data).to_le_bytes());
+    zip64.extend([0xff; 8]);
+    zip64.extend([5, 0, 20, 0]);
+    zip64.extend(b"a.npy");
+    zip64.extend([1, 0, 16, 0]);
+    zip64.extend((data.len() as u64).to_le_bytes());
+    zip64.extend((data.len() as u64).to_le_bytes());
+    zip64.extend(&data);
+    zip64.extend(END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+    assert_eq!(
+        Matrix::read_npz(&zip64[..]).unwrap(),
+        [("a".to_string(), a)]
+    );
+
+    // deflated members (`np.savez_compressed`)
+    bytes[8] = 8;
+    assert!(matches!(
+        Matrix::read_npz(&bytes[..]),
+        Err(Error::Unsupported(_))
+    ));
+}
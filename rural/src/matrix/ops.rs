@@ -0,0 +1,365 @@
+//! Elementwise arithmetic with NumPy-style broadcasting.
+//!
+//! Two shapes are compatible if, along each axis, they are equal or one of them is `1` (e.g. a
+//! `(1, n)` bias row can be added to every row of an `(m, n)` batch).
+
+use super::{Matrix, MatrixView, MatrixViewMut};
+use crate::error::{Error, OrPanic, Result};
+use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// Returns the shape resulting from broadcasting `a` against `b`, if they are compatible.
+pub fn broadcast_shape(a: (usize, usize), b: (usize, usize)) -> Option<(usize, usize)> {
+    fn axis(a: usize, b: usize) -> Option<usize> {
+        match (a, b) {
+            (a, b) if a == b => Some(a),
+            (1, b) => Some(b),
+            (a, 1) => Some(a),
+            _ => None,
+        }
+    }
+
+    Some((axis(a.0, b.0)?, axis(a.1, b.1)?))
+}
+
+// index into `v` for position `(i, j)` of the broadcast result
+#[inline]
+fn at(v: &MatrixView, (i, j): (usize, usize)) -> f32 {
+    let (m, n) = v.shape();
+    v[(if m == 1 { 0 } else { i }, if n == 1 { 0 } else { j })]
+}
+
+impl MatrixView<'_> {
+    // NOTE: `+`/`-` on views are provided through `std::ops` below
+    /// Combines two (broadcast-compatible) matrices elementwise.
+    pub fn zip_with<'b>(
+        self,
+        other: impl Into<MatrixView<'b>>,
+        f: impl Fn(f32, f32) -> f32,
+    ) -> Matrix {
+        self.try_zip_with(other, f).or_panic()
+    }
+
+    pub fn try_zip_with<'b>(
+        self,
+        other: impl Into<MatrixView<'b>>,
+        f: impl Fn(f32, f32) -> f32,
+    ) -> Result<Matrix> {
+        let other = other.into();
+
+        let (m, n) = broadcast_shape(self.shape(), other.shape()).ok_or(Error::ShapeMismatch {
+            lhs: self.shape(),
+            rhs: other.shape(),
+        })?;
+
+        let mut result = Matrix::zeroes(m, n);
+
+        for i in 0..m {
+            for j in 0..n {
+                result[(i, j)] = f(at(&self, (i, j)), at(&other, (i, j)));
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Elementwise (Hadamard) product.
+    pub fn hadamard<'b>(self, other: impl Into<MatrixView<'b>>) -> Matrix {
+        self.zip_with(other, |x, y| x * y)
+    }
+
+    pub fn try_hadamard<'b>(self, other: impl Into<MatrixView<'b>>) -> Result<Matrix> {
+        self.try_zip_with(other, |x, y| x * y)
+    }
+
+    pub fn scale(self, alpha: f32) -> Matrix {
+        self.map(|x| alpha * x)
+    }
+
+    /// Applies `f` to every element, e.g. `m.map(rural::activation::pade::tanhf)`.
+    pub fn map(self, f: impl Fn(f32) -> f32) -> Matrix {
+        let (m, n) = self.shape();
+
+        let mut result = Matrix::zeroes(m, n);
+
+        for i in 0..m {
+            for j in 0..n {
+                result[(i, j)] = f(self[(i, j)]);
+            }
+        }
+
+        result
+    }
+}
+
+impl MatrixViewMut<'_> {
+    /// Combines `other` into `self` elementwise; `other` must broadcast to the shape of `self`.
+    pub fn zip_with_inplace<'b>(
+        &mut self,
+        other: impl Into<MatrixView<'b>>,
+        f: impl Fn(f32, f32) -> f32,
+    ) {
+        self.try_zip_with_inplace(other, f).or_panic()
+    }
+
+    pub fn try_zip_with_inplace<'b>(
+        &mut self,
+        other: impl Into<MatrixView<'b>>,
+        f: impl Fn(f32, f32) -> f32,
+    ) -> Result<()> {
+        let other = other.into();
+
+        if broadcast_shape(self.shape(), other.shape()) != Some(self.shape()) {
+            return Err(Error::ShapeMismatch {
+                lhs: self.shape(),
+                rhs: other.shape(),
+            });
+        }
+
+        let (m, n) = self.shape();
+
+        for i in 0..m {
+            for j in 0..n {
+                self[(i, j)] = f(self[(i, j)], at(&other, (i, j)));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn map_inplace(&mut self, f: impl Fn(f32) -> f32) {
+        let (m, n) = self.shape();
+
+        for i in 0..m {
+            for j in 0..n {
+                self[(i, j)] = f(self[(i, j)]);
+            }
+        }
+    }
+}
+
+impl Matrix {
+    pub fn zip_with<'b>(
+        &self,
+        other: impl Into<MatrixView<'b>>,
+        f: impl Fn(f32, f32) -> f32,
+    ) -> Matrix {
+        self.view().zip_with(other, f)
+    }
+
+    pub fn try_zip_with<'b>(
+        &self,
+        other: impl Into<MatrixView<'b>>,
+        f: impl Fn(f32, f32) -> f32,
+    ) -> Result<Matrix> {
+        self.view().try_zip_with(other, f)
+    }
+
+    pub fn add<'b>(&self, other: impl Into<MatrixView<'b>>) -> Matrix {
+        self.view().zip_with(other, |x, y| x + y)
+    }
+
+    pub fn try_add<'b>(&self, other: impl Into<MatrixView<'b>>) -> Result<Matrix> {
+        self.view().try_zip_with(other, |x, y| x + y)
+    }
+
+    pub fn sub<'b>(&self, other: impl Into<MatrixView<'b>>) -> Matrix {
+        self.view().zip_with(other, |x, y| x - y)
+    }
+
+    pub fn try_sub<'b>(&self, other: impl Into<MatrixView<'b>>) -> Result<Matrix> {
+        self.view().try_zip_with(other, |x, y| x - y)
+    }
+
+    pub fn hadamard<'b>(&self, other: impl Into<MatrixView<'b>>) -> Matrix {
+        self.view().hadamard(other)
+    }
+
+    pub fn try_hadamard<'b>(&self, other: impl Into<MatrixView<'b>>) -> Result<Matrix> {
+        self.view().try_hadamard(other)
+    }
+
+    pub fn scale(&self, alpha: f32) -> Matrix {
+        self.view().scale(alpha)
+    }
+
+    pub fn map(&self, f: impl Fn(f32) -> f32) -> Matrix {
+        self.view().map(f)
+    }
+
+    pub fn map_inplace(&mut self, f: impl Fn(f32) -> f32) {
+        self.data.iter_mut().for_each(|x| *x = f(*x));
+    }
+}
+
+// `lhs op rhs` for combinations of matrices and views
+//
+// NOTE: there are no impls for an owned `Matrix` on the left, since those would shadow the
+// inherent (borrowing) `Matrix::add`/`Matrix::sub` - use `&a + &b` instead.
+macro_rules! impl_binary_op {
+    ($Op:ident, $op:ident, $f:expr) => {
+        impl_binary_op!(@impl $Op, $op, $f, [&Matrix] [Matrix]);
+        impl_binary_op!(@impl $Op, $op, $f, [&Matrix] [&Matrix]);
+        impl_binary_op!(@impl $Op, $op, $f, [&Matrix] [MatrixView<'_>]);
+        impl_binary_op!(@impl $Op, $op, $f, [MatrixView<'_>] [Matrix]);
+        impl_binary_op!(@impl $Op, $op, $f, [MatrixView<'_>] [&Matrix]);
+        impl_binary_op!(@impl $Op, $op, $f, [MatrixView<'_>] [MatrixView<'_>]);
+    };
+    (@impl $Op:ident, $op:ident, $f:expr, [$($Lhs:tt)+] [$($Rhs:tt)+]) => {
+        impl $Op<$($Rhs)+> for $($Lhs)+ {
+            type Output = Matrix;
+            fn $op(self, rhs: $($Rhs)+) -> Matrix {
+                let (lhs, rhs) = (&self, &rhs);
+                lhs.view().zip_with(rhs.view(), $f)
+            }
+        }
+    };
+}
+
+impl_binary_op!(Add, add, |x, y| x + y);
+impl_binary_op!(Sub, sub, |x, y| x - y);
+
+macro_rules! impl_assign_op {
+    ($Op:ident, $op:ident, $f:expr) => {
+        impl $Op<&Matrix> for Matrix {
+            fn $op(&mut self, rhs: &Matrix) {
+                self.view_mut().zip_with_inplace(rhs, $f);
+            }
+        }
+        impl $Op<MatrixView<'_>> for Matrix {
+            fn $op(&mut self, rhs: MatrixView<'_>) {
+                self.view_mut().zip_with_inplace(rhs, $f);
+            }
+        }
+    };
+}
+
+impl_assign_op!(AddAssign, add_assign, |x, y| x + y);
+impl_assign_op!(SubAssign, sub_assign, |x, y| x - y);
+
+impl Mul<f32> for Matrix {
+    type Output = Matrix;
+    fn mul(mut self, rhs: f32) -> Matrix {
+        self *= rhs;
+        self
+    }
+}
+impl Mul<f32> for &Matrix {
+    type Output = Matrix;
+    fn mul(self, rhs: f32) -> Matrix {
+        self.scale(rhs)
+    }
+}
+impl Mul<f32> for MatrixView<'_> {
+    type Output = Matrix;
+    fn mul(self, rhs: f32) -> Matrix {
+        self.scale(rhs)
+    }
+}
+impl Mul<Matrix> for f32 {
+    type Output = Matrix;
+    fn mul(self, rhs: Matrix) -> Matrix {
+        rhs * self
+    }
+}
+impl Mul<&Matrix> for f32 {
+    type Output = Matrix;
+    fn mul(self, rhs: &Matrix) -> Matrix {
+        rhs * self
+    }
+}
+
+impl MulAssign<f32> for Matrix {
+    fn mul_assign(&mut self, rhs: f32) {
+        self.map_inplace(|x| x * rhs);
+    }
+}
+
+impl Neg for Matrix {
+    type Output = Matrix;
+    fn neg(mut self) -> Matrix {
+        self.map_inplace(|x| -x);
+        self
+    }
+}
+impl Neg for &Matrix {
+    type Output = Matrix;
+    fn neg(self) -> Matrix {
+        self.map(|x| -x)
+    }
+}
+impl Neg for MatrixView<'_> {
+    type Output = Matrix;
+    fn neg(self) -> Matrix {
+        self.map(|x| -x)
+    }
+}
+
+// lets the operator macros treat owned matrices, references and views uniformly
+trait AsView {
+    fn view(&self) -> MatrixView<'_>;
+}
+impl AsView for Matrix {
+    fn view(&self) -> MatrixView<'_> {
+        Matrix::view(self)
+    }
+}
+impl AsView for &Matrix {
+    fn view(&self) -> MatrixView<'_> {
+        Matrix::view(self)
+    }
+}
+impl AsView for MatrixView<'_> {
+    fn view(&self) -> MatrixView<'_> {
+        *self
+    }
+}
+
+#[test]
+fn broadcasting() {
+    let a = Matrix::new(&[[1., 2., 3.], [4., 5., 6.]]);
+    let row = Matrix::new(&[[10., 20., 30.]]);
+    let col = Matrix::new(&[[100.], [200.]]);
+
+    assert_eq!(broadcast_shape((2, 3), (1, 3)), Some((2, 3)));
+    assert_eq!(broadcast_shape((2, 1), (1, 3)), Some((2, 3)));
+    assert_eq!(broadcast_shape((2, 3), (3, 2)), None);
+
+    assert_eq!(&a + &row, Matrix::new(&[[11., 22., 33.], [14., 25., 36.]]));
+    assert_eq!(
+        &a - &col,
+        Matrix::new(&[[-99., -98., -97.], [-196., -195., -194.]])
+    );
+    assert_eq!(
+        row.add(&col),
+        Matrix::new(&[[110., 120., 130.], [210., 220., 230.]])
+    );
+    assert_eq!(
+        a.hadamard(&Matrix::new(&[[2.]])),
+        Matrix::new(&[[2., 4., 6.], [8., 10., 12.]])
+    );
+    assert_eq!(
+        a.transpose() + a.transpose(),
+        (&a * 2.).transpose().to_matrix()
+    );
+
+    let mut b = a.clone();
+    b += &row;
+    b -= a.slice(.., ..1);
+    b *= 0.5;
+    assert_eq!(b, Matrix::new(&[[5., 10.5, 16.], [5., 10.5, 16.]]));
+    assert_eq!((-&b).add(&b), Matrix::zeroes(2, 3));
+}
+
+#[test]
+fn map_activation() {
+    use crate::activation::pade;
+
+    let a = Matrix::new(&[[-1., 0., 1.]]);
+
+    let mut b = a.clone();
+    b.map_inplace(pade::tanhf);
+
+    assert_eq!(a.map(pade::tanhf), b);
+    assert_eq!(b[(0, 1)], 0.);
+    assert_eq!(b[(0, 0)], -b[(0, 2)]);
+}
@@ -0,0 +1,329 @@
+//! Multiplication-free approximate matmul against fixed weights, via product quantization.
+//! * 'Multiplying Matrices Without Multiplying' (Blalock, Guttag) - MADDNESS
+//!
+//! The input dimension is split into `C` contiguous subspaces. Within each one, a balanced binary
+//! hash tree (one split dimension per level, one threshold per node) assigns every input row to one
+//! of `2^levels` prototypes. The products of all prototypes with the weights are precomputed into
+//! lookup tables, so inference only encodes the input (comparisons) and sums table rows.
+//!
+//! NOTE: the thresholds are bucket medians and the tables are kept in `f32` (the paper optimizes
+//! thresholds for the SSE, refines prototypes with ridge regression, and quantizes the tables).
+
+use super::{Matrix, MatrixView};
+use crate::error::{check_matmul, Error, OrPanic, Result};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PqOptions {
+    /// Number of subspaces (codebooks).
+    pub codebooks: usize,
+    /// Depth of the hash trees (`2^levels` prototypes per codebook, at most 8).
+    pub levels: usize,
+}
+
+impl Default for PqOptions {
+    fn default() -> Self {
+        Self {
+            codebooks: 16,
+            levels: 4,
+        }
+    }
+}
+
+/// Trained encoder and lookup tables for a fixed weight matrix.
+#[derive(Clone, Debug)]
+pub struct Maddness {
+    // column range of every subspace
+    subspaces: Box<[(usize, usize)]>,
+    levels: usize,
+    // split dimension (absolute column) per codebook and level
+    split_dims: Box<[usize]>,
+    // `2^levels - 1` thresholds per codebook, in heap order
+    thresholds: Box<[f32]>,
+    // `(C * 2^levels, p)`: prototype·weights for every codebook and prototype
+    luts: Matrix,
+}
+
+// learns the hash tree of one subspace; returns the split dimensions, thresholds and the bucket of
+// every sample
+fn learn_tree(
+    x: MatrixView,
+    (start, end): (usize, usize),
+    levels: usize,
+) -> (Vec<usize>, Vec<f32>, Vec<usize>) {
+    let (n, _d) = x.shape();
+
+    let mut split_dims = vec![];
+    let mut thresholds = vec![];
+    let mut buckets = vec![0; n];
+
+    for level in 0..levels {
+        let nodes = 1 << level;
+
+        // the dimension with the largest total within-bucket variance
+        let sse = |dim: usize| -> f32 {
+            let mut sum = vec![0f32; nodes];
+            let mut sum_sq = vec![0f32; nodes];
+            let mut count = vec![0f32; nodes];
+            for (i, &b) in buckets.iter().enumerate() {
+                let v = x[(i, dim)];
+                sum[b] += v;
+                sum_sq[b] += v * v;
+                count[b] += 1.;
+            }
+            (0..nodes)
+                .filter(|&b| count[b] > 0.)
+                .map(|b| sum_sq[b] - sum[b] * sum[b] / count[b])
+                .sum()
+        };
+        let dim = (start..end)
+            .max_by(|&a, &b| sse(a).total_cmp(&sse(b)))
+            .unwrap_or(start);
+
+        let mut values = vec![vec![]; nodes];
+        for (i, &b) in buckets.iter().enumerate() {
+            values[b].push(x[(i, dim)]);
+        }
+
+        let level_thresholds: Vec<f32> = values
+            .iter_mut()
+            .map(|v| {
+                if v.is_empty() {
+                    return 0.;
+                }
+                // lower median, so that ties go left and an even split stays even
+                let mid = (v.len() - 1) / 2;
+                *v.select_nth_unstable_by(mid, f32::total_cmp).1
+            })
+            .collect();
+
+        for (i, b) in buckets.iter_mut().enumerate() {
+            *b = 2 * *b + (x[(i, dim)] > level_thresholds[*b]) as usize;
+        }
+
+        split_dims.push(dim);
+        thresholds.extend(level_thresholds);
+    }
+
+    (split_dims, thresholds, buckets)
+}
+
+impl Maddness {
+    /// Learns the encoders from sample input rows `x` (`(N, n)`) and precomputes the lookup tables
+    /// for `weights` (`(n, p)`).
+    pub fn train<'a, 'b>(
+        x: impl Into<MatrixView<'a>>,
+        weights: impl Into<MatrixView<'b>>,
+        options: PqOptions,
+    ) -> Self {
+        Self::try_train(x, weights, options).or_panic()
+    }
+
+    pub fn try_train<'a, 'b>(
+        x: impl Into<MatrixView<'a>>,
+        weights: impl Into<MatrixView<'b>>,
+        options: PqOptions,
+    ) -> Result<Self> {
+        let (x, weights) = (x.into(), weights.into());
+        check_matmul(x.shape(), weights.shape())?;
+
+        let (samples, n) = x.shape();
+        let (_n, p) = weights.shape();
+
+        if samples == 0 {
+            return Err(Error::EmptyInput);
+        }
+        if !(0 < options.codebooks && options.codebooks <= n) {
+            return Err(Error::InvalidArgument(format!(
+                "{} codebooks for {n} input dimensions",
+                options.codebooks
+            )));
+        }
+        if !(0 < options.levels && options.levels <= 8) {
+            return Err(Error::InvalidArgument(format!(
+                "{} levels (not in 1..=8)",
+                options.levels
+            )));
+        }
+
+        let c = options.codebooks;
+        let leaves = 1 << options.levels;
+
+        let subspaces: Box<[(usize, usize)]> =
+            (0..c).map(|i| (i * n / c, (i + 1) * n / c)).collect();
+
+        let mut split_dims = vec![];
+        let mut thresholds = vec![];
+        let mut luts = Matrix::zeroes(c * leaves, p);
+
+        for (codebook, &(start, end)) in subspaces.iter().enumerate() {
+            let (dims, t, buckets) = learn_tree(x, (start, end), options.levels);
+            split_dims.extend(dims);
+            thresholds.extend(t);
+
+            // prototypes: mean of the subvectors in every bucket
+            let mut prototypes = Matrix::zeroes(leaves, end - start);
+            let mut count = vec![0; leaves];
+            for (i, &b) in buckets.iter().enumerate() {
+                for (j, y) in prototypes[b].iter_mut().enumerate() {
+                    *y += x[(i, start + j)];
+                }
+                count[b] += 1;
+            }
+            for (b, &count) in count.iter().enumerate() {
+                if count > 0 {
+                    prototypes[b].iter_mut().for_each(|y| *y /= count as f32);
+                }
+            }
+
+            let lut = prototypes.matmul(weights.slice(start..end, ..));
+            luts.slice_mut(codebook * leaves..(codebook + 1) * leaves, ..)
+                .copy_from(&lut);
+        }
+
+        Ok(Self {
+            subspaces,
+            levels: options.levels,
+            split_dims: split_dims.into(),
+            thresholds: thresholds.into(),
+            luts,
+        })
+    }
+
+    /// Shape `(n, p)` of the weights.
+    pub fn shape(&self) -> (usize, usize) {
+        let n = self.subspaces.last().map_or(0, |&(_, end)| end);
+        (n, self.luts.shape().1)
+    }
+
+    /// Output size `p`.
+    pub fn output_size(&self) -> usize {
+        self.luts.shape().1
+    }
+
+    pub fn codebooks(&self) -> usize {
+        self.subspaces.len()
+    }
+
+    /// Prototype index of every row and codebook (`(m, C)`, row-major).
+    pub fn encode<'a>(&self, x: impl Into<MatrixView<'a>>) -> Box<[u8]> {
+        self.try_encode(x).or_panic()
+    }
+
+    pub fn try_encode<'a>(&self, x: impl Into<MatrixView<'a>>) -> Result<Box<[u8]>> {
+        let x = x.into();
+        check_matmul(x.shape(), self.shape())?;
+
+        let (m, _n) = x.shape();
+        let c = self.codebooks();
+        let internal = (1 << self.levels) - 1;
+
+        let mut codes = vec![0u8; m * c].into_boxed_slice();
+
+        for i in 0..m {
+            for codebook in 0..c {
+                let dims = &self.split_dims[codebook * self.levels..(codebook + 1) * self.levels];
+                let thresholds = &self.thresholds[codebook * internal..(codebook + 1) * internal];
+
+                let mut node = 0;
+                for (level, &dim) in dims.iter().enumerate() {
+                    let threshold = thresholds[(1 << level) - 1 + node];
+                    node = 2 * node + (x[(i, dim)] > threshold) as usize;
+                }
+
+                codes[i * c + codebook] = node as u8;
+            }
+        }
+
+        Ok(codes)
+    }
+
+    /// Approximates `x·weights` by summing lookup table rows.
+    pub fn matmul<'a>(&self, x: impl Into<MatrixView<'a>>) -> Matrix {
+        self.try_matmul(x).or_panic()
+    }
+
+    pub fn try_matmul<'a>(&self, x: impl Into<MatrixView<'a>>) -> Result<Matrix> {
+        let x = x.into();
+        let codes = self.try_encode(x)?;
+
+        let (m, _n) = x.shape();
+        let (c, p) = (self.codebooks(), self.output_size());
+        let leaves = 1 << self.levels;
+
+        let mut result = Matrix::zeroes(m, p);
+        for i in 0..m {
+            let row = &mut result[i];
+            for codebook in 0..c {
+                let lut = &self.luts[codebook * leaves + codes[i * c + codebook] as usize];
+                for (y, &t) in row.iter_mut().zip(lut) {
+                    *y += t;
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl Matrix {
+    pub fn maddness_matmul(&self, maddness: &Maddness) -> Matrix {
+        maddness.matmul(self)
+    }
+
+    pub fn try_maddness_matmul(&self, maddness: &Maddness) -> Result<Matrix> {
+        maddness.try_matmul(self)
+    }
+}
+
+#[test]
+fn maddness() {
+    let weights = Matrix::random(32, 6, 0);
+
+    // inputs that are exactly one of 4 patterns per subspace are reconstructed exactly
+    let patterns = Matrix::random(4, 32, 1);
+    let mut x = Matrix::zeroes(64, 32);
+    for i in 0..64 {
+        for codebook in 0..4 {
+            let pattern = (i + codebook) % 4;
+            for j in codebook * 8..(codebook + 1) * 8 {
+                x[(i, j)] = patterns[(pattern, j)];
+            }
+        }
+    }
+
+    let options = PqOptions {
+        codebooks: 4,
+        levels: 2,
+    };
+    let maddness = Maddness::train(&x, &weights, options);
+
+    let codes = maddness.encode(&x);
+    assert_eq!(codes.len(), 64 * 4);
+    assert!(codes.iter().all(|&code| code < 4));
+
+    let expected = x.matmul(&weights);
+    let actual = x.maddness_matmul(&maddness);
+    assert!(expected.sub(&actual).data.iter().all(|x| x.abs() < 1e-4));
+
+    // more prototypes don't increase the error on generic inputs
+    let x = Matrix::random(256, 32, 2);
+    let expected = x.matmul(&weights);
+    let error = |levels| {
+        let maddness = Maddness::train(
+            &x,
+            &weights,
+            PqOptions {
+                codebooks: 8,
+                levels,
+            },
+        );
+        let actual = maddness.matmul(&x);
+        expected
+            .sub(&actual)
+            .data
+            .iter()
+            .map(|x| x * x)
+            .sum::<f32>()
+    };
+    assert!(error(4) < error(2));
+}
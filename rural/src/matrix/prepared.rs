@@ -0,0 +1,234 @@
+//! Precomputed statistics for repeated [`Matrix::rand_matmul`] against a static right-hand
+//! operand (e.g. the weights of a dense layer during inference).
+//!
+//! The row norms of the weights are computed once, so each call only has to compute the column
+//! norms of its input. A [`FixedPlan`] goes further and picks the sampled rows up front, based on
+//! the weights alone.
+
+use super::sampling::{indices, num_samples, outer_products, probabilities, Sampling};
+use super::{Matrix, MatrixView, Norm};
+use crate::error::{check_factor, check_matmul, OrPanic, Result};
+use rand_core::{RngCore, SeedableRng};
+
+/// Weight matrix with cached row norms.
+#[derive(Clone, Debug)]
+pub struct PreparedMatrix {
+    matrix: Matrix,
+    row_norm: Box<[f32]>,
+}
+
+/// Input-independent sampling plan: the `c` rows of the weights with the largest norms, gathered
+/// into a contiguous `(c, p)` matrix.
+///
+/// NOTE: like [`Sampling::TopC`], this is biased (no rescaling), but exact at `factor = 1`.
+#[derive(Clone, Debug)]
+pub struct FixedPlan {
+    indices: Box<[usize]>,
+    rows: Matrix,
+    // number of rows of the weights
+    n: usize,
+}
+
+impl PreparedMatrix {
+    pub fn new(matrix: Matrix) -> Self {
+        let row_norm = matrix.row_norms(Norm::L2).data;
+
+        Self { matrix, row_norm }
+    }
+
+    pub fn matrix(&self) -> &Matrix {
+        &self.matrix
+    }
+
+    pub fn row_norms(&self) -> &[f32] {
+        &self.row_norm
+    }
+
+    pub fn shape(&self) -> (usize, usize) {
+        self.matrix.shape()
+    }
+
+    pub fn fixed_plan(&self, factor: f32) -> FixedPlan {
+        self.try_fixed_plan(factor).or_panic()
+    }
+
+    pub fn try_fixed_plan(&self, factor: f32) -> Result<FixedPlan> {
+        check_factor(factor)?;
+
+        let (n, p) = self.matrix.shape();
+        let c = num_samples(n, factor);
+
+        let mut indices = indices(&self.row_norm, c);
+        indices.sort_unstable();
+
+        let mut rows = Matrix::zeroes(c, p);
+        for (r, &k) in indices.iter().enumerate() {
+            rows[r].copy_from_slice(&self.matrix[k]);
+        }
+
+        Ok(FixedPlan { indices, rows, n })
+    }
+}
+
+impl FixedPlan {
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+}
+
+impl Matrix {
+    pub fn rand_matmul_prepared(&self, other: &PreparedMatrix, factor: f32) -> Matrix {
+        self.view().rand_matmul_prepared(other, factor)
+    }
+
+    pub fn try_rand_matmul_prepared(&self, other: &PreparedMatrix, factor: f32) -> Result<Matrix> {
+        self.view().try_rand_matmul_prepared(other, factor)
+    }
+
+    pub fn rand_matmul_prepared_with(
+        &self,
+        other: &PreparedMatrix,
+        factor: f32,
+        sampling: Sampling,
+        rng: &mut impl RngCore,
+    ) -> Matrix {
+        self.view()
+            .rand_matmul_prepared_with(other, factor, sampling, rng)
+    }
+
+    pub fn try_rand_matmul_prepared_with(
+        &self,
+        other: &PreparedMatrix,
+        factor: f32,
+        sampling: Sampling,
+        rng: &mut impl RngCore,
+    ) -> Result<Matrix> {
+        self.view()
+            .try_rand_matmul_prepared_with(other, factor, sampling, rng)
+    }
+
+    pub fn rand_matmul_fixed(&self, plan: &FixedPlan) -> Matrix {
+        self.view().rand_matmul_fixed(plan)
+    }
+
+    pub fn try_rand_matmul_fixed(&self, plan: &FixedPlan) -> Result<Matrix> {
+        self.view().try_rand_matmul_fixed(plan)
+    }
+}
+
+impl MatrixView<'_> {
+    pub fn rand_matmul_prepared(&self, other: &PreparedMatrix, factor: f32) -> Matrix {
+        self.try_rand_matmul_prepared(other, factor).or_panic()
+    }
+
+    pub fn try_rand_matmul_prepared(&self, other: &PreparedMatrix, factor: f32) -> Result<Matrix> {
+        // NOTE: `TopC` doesn't draw from the RNG
+        let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+        self.try_rand_matmul_prepared_with(other, factor, Sampling::TopC, &mut rng)
+    }
+
+    pub fn rand_matmul_prepared_with(
+        &self,
+        other: &PreparedMatrix,
+        factor: f32,
+        sampling: Sampling,
+        rng: &mut impl RngCore,
+    ) -> Matrix {
+        self.try_rand_matmul_prepared_with(other, factor, sampling, rng)
+            .or_panic()
+    }
+
+    pub fn try_rand_matmul_prepared_with(
+        &self,
+        other: &PreparedMatrix,
+        factor: f32,
+        sampling: Sampling,
+        rng: &mut impl RngCore,
+    ) -> Result<Matrix> {
+        check_matmul(self.shape(), other.shape())?;
+        check_factor(factor)?;
+
+        let (_m, n) = self.shape();
+
+        let prob = probabilities(&self.col_norms(Norm::L2).data, &other.row_norm);
+        let c = num_samples(n, factor);
+
+        Ok(outer_products(
+            *self,
+            other.matrix.view(),
+            &sampling.plan(&prob, c, rng),
+        ))
+    }
+
+    pub fn rand_matmul_fixed(&self, plan: &FixedPlan) -> Matrix {
+        self.try_rand_matmul_fixed(plan).or_panic()
+    }
+
+    pub fn try_rand_matmul_fixed(&self, plan: &FixedPlan) -> Result<Matrix> {
+        let (_c, p) = plan.rows.shape();
+        check_matmul(self.shape(), (plan.n, p))?;
+
+        let (m, _n) = self.shape();
+
+        let mut result = Matrix::zeroes(m, p);
+
+        for (r, &t) in plan.indices.iter().enumerate() {
+            let row = &plan.rows[r];
+            for i in 0..m {
+                let a = self[(i, t)];
+                for (y, b) in result[i].iter_mut().zip(row) {
+                    *y += a * b;
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+#[test]
+fn prepared_matches_unprepared() {
+    let a = Matrix::random(3, 16, 0);
+    let b = Matrix::random(16, 5, 1);
+
+    let prepared = PreparedMatrix::new(b.clone());
+    assert_eq!(prepared.row_norms(), &*b.row_norms(Norm::L2).data);
+
+    for factor in [0.25, 0.5, 1.] {
+        assert_eq!(
+            a.rand_matmul_prepared(&prepared, factor),
+            a.rand_matmul(&b, factor)
+        );
+
+        let mut rng0 = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+        let mut rng1 = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+        assert_eq!(
+            a.rand_matmul_prepared_with(&prepared, factor, Sampling::WithReplacement, &mut rng0),
+            a.rand_matmul_with(&b, factor, Sampling::WithReplacement, &mut rng1)
+        );
+    }
+}
+
+#[test]
+fn fixed_plan() {
+    let a = Matrix::random(3, 16, 0);
+    let b = Matrix::random(16, 5, 1);
+
+    let prepared = PreparedMatrix::new(b.clone());
+
+    let plan = prepared.fixed_plan(0.25);
+    assert_eq!(plan.indices().len(), 4);
+
+    // same as a full product restricted to the planned rows
+    let mut expected = Matrix::zeroes(3, 5);
+    for &k in plan.indices() {
+        expected += &a.slice(.., k..=k).matmul(b.slice(k..=k, ..));
+    }
+    let error = a.rand_matmul_fixed(&plan).sub(&expected);
+    assert!(error.data.iter().all(|x| x.abs() < 1e-6));
+
+    let error = a
+        .rand_matmul_fixed(&prepared.fixed_plan(1.))
+        .sub(&a.matmul(&b));
+    assert!(error.data.iter().all(|x| x.abs() < 1e-5));
+}
@@ -0,0 +1,518 @@
+//! Int8 quantization: `x ≈ scale * (q - zero_point)` with `q ∈ [-128, 127]`.
+//! * 'Quantization and Training of Neural Networks for Efficient Integer-Arithmetic-Only
+//!   Inference' (Jacob, et al.)
+//!
+//! The product of two quantized matrices is computed with `i32` accumulators; the zero points are
+//! folded out of the inner sum, so the scales must be constant along the reduction dimension (per
+//! row for the left operand, per column for the right one).
+
+use super::{Matrix, MatrixView};
+use crate::error::{check_matmul, Error, OrPanic, Result};
+
+/// How the real range is mapped onto `i8`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scheme {
+    /// `[-max|x|, max|x|] -> [-127, 127]`, zero point 0.
+    #[default]
+    Symmetric,
+    /// `[min, max] -> [-128, 127]` (the range is extended to include 0, so it is exact).
+    Asymmetric,
+}
+
+/// Which elements share a scale (and zero point).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Granularity {
+    #[default]
+    PerTensor,
+    PerRow,
+    PerColumn,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuantParams {
+    pub scale: f32,
+    pub zero_point: i32,
+}
+
+/// Int8 matrix with its quantization parameters.
+#[derive(Clone, Debug)]
+pub struct QuantizedMatrix {
+    data: Box<[i8]>,
+    shape: (usize, usize),
+    granularity: Granularity,
+    params: Box<[QuantParams]>,
+}
+
+/// Running range of sample activations, used to calibrate the (per-tensor) quantization
+/// parameters of inputs that are only known at inference time.
+#[derive(Clone, Debug)]
+pub struct Observer {
+    min: f32,
+    max: f32,
+    // exponential moving average of the per-batch range, instead of the global range
+    momentum: Option<f32>,
+}
+
+impl QuantParams {
+    pub fn from_range(min: f32, max: f32, scheme: Scheme) -> Self {
+        let (min, max) = (min.min(0.), max.max(0.));
+
+        let (scale, zero_point) = match scheme {
+            Scheme::Symmetric => (min.abs().max(max) / 127., 0),
+            Scheme::Asymmetric => {
+                let scale = (max - min) / 255.;
+                let zero_point = if scale > 0. {
+                    (-128. - min / scale).round() as i32
+                } else {
+                    0
+                };
+                (scale, zero_point.clamp(-128, 127))
+            }
+        };
+
+        // all-zero input; any scale works
+        let scale = if scale > 0. { scale } else { 1. };
+
+        Self { scale, zero_point }
+    }
+
+    pub fn quantize(&self, x: f32) -> i8 {
+        // NOTE: the cast saturates for outliers (and infinities), so adding the zero point mustn't
+        // overflow
+        ((x / self.scale).round() as i32)
+            .saturating_add(self.zero_point)
+            .clamp(-128, 127) as i8
+    }
+
+    pub fn dequantize(&self, q: i8) -> f32 {
+        self.scale * (q as i32 - self.zero_point) as f32
+    }
+}
+
+impl QuantizedMatrix {
+    pub fn new<'a>(m: impl Into<MatrixView<'a>>, scheme: Scheme, granularity: Granularity) -> Self {
+        let m = m.into();
+        let (rows, cols) = m.shape();
+
+        let range = |values: &mut dyn Iterator<Item = f32>| {
+            let (min, max) = values.fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), x| {
+                (min.min(x), max.max(x))
+            });
+            QuantParams::from_range(min, max, scheme)
+        };
+
+        let params: Box<[QuantParams]> = match granularity {
+            Granularity::PerTensor => {
+                let mut values = (0..rows).flat_map(|i| (0..cols).map(move |j| m[(i, j)]));
+                [range(&mut values)].into()
+            }
+            Granularity::PerRow => (0..rows)
+                .map(|i| range(&mut (0..cols).map(|j| m[(i, j)])))
+                .collect(),
+            Granularity::PerColumn => (0..cols)
+                .map(|j| range(&mut (0..rows).map(|i| m[(i, j)])))
+                .collect(),
+        };
+
+        Self::with_params(m, granularity, params)
+    }
+
+    /// Quantizes with given (e.g. calibrated) parameters; one per row/column for
+    /// [`Granularity::PerRow`]/[`Granularity::PerColumn`].
+    pub fn with_params<'a>(
+        m: impl Into<MatrixView<'a>>,
+        granularity: Granularity,
+        params: impl Into<Box<[QuantParams]>>,
+    ) -> Self {
+        Self::try_with_params(m, granularity, params).or_panic()
+    }
+
+    pub fn try_with_params<'a>(
+        m: impl Into<MatrixView<'a>>,
+        granularity: Granularity,
+        params: impl Into<Box<[QuantParams]>>,
+    ) -> Result<Self> {
+        let m = m.into();
+        let params = params.into();
+        let (rows, cols) = m.shape();
+
+        let expected = match granularity {
+            Granularity::PerTensor => 1,
+            Granularity::PerRow => rows,
+            Granularity::PerColumn => cols,
+        };
+        if params.len() != expected {
+            return Err(Error::InvalidArgument(format!(
+                "{} quantization parameters for {granularity:?} (expected {expected})",
+                params.len()
+            )));
+        }
+        if let Some(p) = params.iter().find(|p| p.scale.is_nan() || p.scale <= 0.) {
+            return Err(Error::InvalidArgument(format!(
+                "quantization scale {} (not positive)",
+                p.scale
+            )));
+        }
+
+        let mut result = Self {
+            data: vec![0; rows * cols].into(),
+            shape: (rows, cols),
+            granularity,
+            params,
+        };
+
+        for i in 0..rows {
+            for j in 0..cols {
+                result.data[i * cols + j] = result.param(i, j).quantize(m[(i, j)]);
+            }
+        }
+
+        Ok(result)
+    }
+
+    pub fn shape(&self) -> (usize, usize) {
+        self.shape
+    }
+
+    pub fn granularity(&self) -> Granularity {
+        self.granularity
+    }
+
+    pub fn params(&self) -> &[QuantParams] {
+        &self.params
+    }
+
+    /// Quantized values (row-major).
+    pub fn data(&self) -> &[i8] {
+        &self.data
+    }
+
+    fn param(&self, i: usize, j: usize) -> &QuantParams {
+        match self.granularity {
+            Granularity::PerTensor => &self.params[0],
+            Granularity::PerRow => &self.params[i],
+            Granularity::PerColumn => &self.params[j],
+        }
+    }
+
+    pub fn dequantize(&self) -> Matrix {
+        let (m, n) = self.shape;
+
+        let mut result = Matrix::zeroes(m, n);
+        for i in 0..m {
+            for j in 0..n {
+                result[(i, j)] = self.param(i, j).dequantize(self.data[i * n + j]);
+            }
+        }
+
+        result
+    }
+
+    /// Integer product `Σ_k (a_ik - za_i)(b_kj - zb_j)`, i.e. the real product divided by
+    /// `sa_i * sb_j` (row-major, `m × p`).
+    pub fn matmul_i32(&self, other: &QuantizedMatrix) -> Box<[i32]> {
+        self.try_matmul_i32(other).or_panic()
+    }
+
+    pub fn try_matmul_i32(&self, other: &QuantizedMatrix) -> Result<Box<[i32]>> {
+        check_matmul(self.shape, other.shape)?;
+        if self.granularity == Granularity::PerColumn {
+            return Err(Error::InvalidArgument(
+                "the left operand must be quantized per tensor or per row".into(),
+            ));
+        }
+        if other.granularity == Granularity::PerRow {
+            return Err(Error::InvalidArgument(
+                "the right operand must be quantized per tensor or per column".into(),
+            ));
+        }
+
+        let (m, n) = self.shape;
+        let (_n, p) = other.shape;
+
+        let mut b_sums = vec![0i32; p];
+        for row in other.data.chunks_exact(p.max(1)) {
+            for (sum, &b) in b_sums.iter_mut().zip(row) {
+                *sum += b as i32;
+            }
+        }
+
+        let mut result = vec![0; m * p].into_boxed_slice();
+
+        // same loop order as `Matrix::matmul`, with `i32` accumulators
+        for i in 0..m {
+            let acc = &mut result[i * p..(i + 1) * p];
+
+            let mut a_sum = 0;
+            for (k, &a) in self.data[i * n..(i + 1) * n].iter().enumerate() {
+                let a = a as i32;
+                a_sum += a;
+                for (y, &b) in acc.iter_mut().zip(&other.data[k * p..(k + 1) * p]) {
+                    *y += a * b as i32;
+                }
+            }
+
+            let za = self.param(i, 0).zero_point;
+            for (j, y) in acc.iter_mut().enumerate() {
+                let zb = other.param(0, j).zero_point;
+                *y += n as i32 * za * zb - zb * a_sum - za * b_sums[j];
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Product with a real-valued result.
+    pub fn matmul(&self, other: &QuantizedMatrix) -> Matrix {
+        self.try_matmul(other).or_panic()
+    }
+
+    pub fn try_matmul(&self, other: &QuantizedMatrix) -> Result<Matrix> {
+        let acc = self.try_matmul_i32(other)?;
+
+        let (m, _n) = self.shape;
+        let (_n, p) = other.shape;
+
+        let mut result = Matrix::zeroes(m, p);
+        for i in 0..m {
+            for j in 0..p {
+                let scale = self.param(i, 0).scale * other.param(0, j).scale;
+                result[(i, j)] = scale * acc[i * p + j] as f32;
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Product requantized to int8 with the given (per-tensor) output parameters, without going
+    /// through a real-valued intermediate.
+    pub fn matmul_requantize(&self, other: &QuantizedMatrix, output: QuantParams) -> Self {
+        self.try_matmul_requantize(other, output).or_panic()
+    }
+
+    pub fn try_matmul_requantize(
+        &self,
+        other: &QuantizedMatrix,
+        output: QuantParams,
+    ) -> Result<Self> {
+        if output.scale.is_nan() || output.scale <= 0. {
+            return Err(Error::InvalidArgument(format!(
+                "quantization scale {} (not positive)",
+                output.scale
+            )));
+        }
+
+        let acc = self.try_matmul_i32(other)?;
+
+        let (m, _n) = self.shape;
+        let (_n, p) = other.shape;
+
+        let mut data = vec![0; m * p].into_boxed_slice();
+        for i in 0..m {
+            for j in 0..p {
+                let multiplier = self.param(i, 0).scale * other.param(0, j).scale / output.scale;
+                let q = ((multiplier * acc[i * p + j] as f32).round() as i32)
+                    .saturating_add(output.zero_point);
+                data[i * p + j] = q.clamp(-128, 127) as i8;
+            }
+        }
+
+        Ok(Self {
+            data,
+            shape: (m, p),
+            granularity: Granularity::PerTensor,
+            params: [output].into(),
+        })
+    }
+}
+
+impl Default for Observer {
+    fn default() -> Self {
+        Self {
+            min: f32::INFINITY,
+            max: f32::NEG_INFINITY,
+            momentum: None,
+        }
+    }
+}
+
+impl Observer {
+    /// Tracks the global range of all observed values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tracks an exponential moving average of the per-batch range (less sensitive to outliers).
+    pub fn moving_average(momentum: f32) -> Self {
+        debug_assert!((0. ..=1.).contains(&momentum));
+
+        Self {
+            momentum: Some(momentum),
+            ..Self::default()
+        }
+    }
+
+    pub fn observe<'a>(&mut self, m: impl Into<MatrixView<'a>>) {
+        let m = m.into();
+        let (rows, cols) = m.shape();
+
+        let (min, max) = (0..rows)
+            .flat_map(|i| (0..cols).map(move |j| m[(i, j)]))
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), x| {
+                (min.min(x), max.max(x))
+            });
+
+        match self.momentum {
+            Some(momentum) if self.min <= self.max => {
+                self.min += momentum * (min - self.min);
+                self.max += momentum * (max - self.max);
+            }
+            _ => {
+                self.min = self.min.min(min);
+                self.max = self.max.max(max);
+            }
+        }
+    }
+
+    /// Observed `(min, max)`, if anything was observed.
+    pub fn range(&self) -> Option<(f32, f32)> {
+        (self.min <= self.max).then_some((self.min, self.max))
+    }
+
+    pub fn params(&self, scheme: Scheme) -> QuantParams {
+        let (min, max) = self.range().unwrap_or((0., 0.));
+        QuantParams::from_range(min, max, scheme)
+    }
+}
+
+impl Matrix {
+    pub fn quantize(&self, scheme: Scheme, granularity: Granularity) -> QuantizedMatrix {
+        QuantizedMatrix::new(self, scheme, granularity)
+    }
+}
+
+#[test]
+fn quantize_dequantize() {
+    let a = Matrix::new(&[[0.5, -1., 2.], [0., 0.25, 3.]]);
+
+    for scheme in [Scheme::Symmetric, Scheme::Asymmetric] {
+        for granularity in [
+            Granularity::PerTensor,
+            Granularity::PerRow,
+            Granularity::PerColumn,
+        ] {
+            let q = a.quantize(scheme, granularity);
+            let b = q.dequantize();
+
+            for i in 0..2 {
+                for j in 0..3 {
+                    let scale = q.param(i, j).scale;
+                    assert!((a[(i, j)] - b[(i, j)]).abs() <= scale / 2. + f32::EPSILON);
+                }
+            }
+
+            // zero is always exactly representable
+            assert_eq!(b[(1, 0)], 0.);
+        }
+    }
+
+    let q = a.quantize(Scheme::Symmetric, Granularity::PerTensor);
+    assert_eq!(
+        q.params(),
+        &[QuantParams {
+            scale: 3. / 127.,
+            zero_point: 0
+        }]
+    );
+    assert_eq!(q.data()[5], 127);
+
+    // values outside of the calibrated range saturate
+    let params = QuantParams {
+        scale: 0.1,
+        zero_point: 10,
+    };
+    assert_eq!(params.quantize(1e12), 127);
+    assert_eq!(params.quantize(f32::INFINITY), 127);
+    assert_eq!(params.quantize(-1e12), -128);
+    assert_eq!(params.quantize(f32::NEG_INFINITY), -128);
+
+    let q = a.quantize(Scheme::Symmetric, Granularity::PerTensor);
+    let tiny = QuantParams {
+        scale: 1e-30,
+        zero_point: 10,
+    };
+    let qt = a
+        .transpose()
+        .to_matrix()
+        .quantize(Scheme::Symmetric, Granularity::PerTensor);
+    let product = q.matmul_requantize(&qt, tiny);
+    assert!(product.data().iter().all(|&x| x == 127 || x == -128));
+}
+
+#[test]
+fn quantized_matmul() {
+    let a = Matrix::random(8, 32, 0).map(|x| x + 0.5);
+    let b = Matrix::random(32, 6, 1);
+
+    for (scheme, ga, gb) in [
+        (
+            Scheme::Symmetric,
+            Granularity::PerTensor,
+            Granularity::PerTensor,
+        ),
+        (
+            Scheme::Symmetric,
+            Granularity::PerRow,
+            Granularity::PerColumn,
+        ),
+        (
+            Scheme::Asymmetric,
+            Granularity::PerTensor,
+            Granularity::PerColumn,
+        ),
+        (
+            Scheme::Asymmetric,
+            Granularity::PerRow,
+            Granularity::PerTensor,
+        ),
+    ] {
+        let qa = a.quantize(scheme, ga);
+        let qb = b.quantize(scheme, gb);
+
+        // the integer kernel is exact w.r.t. the dequantized operands
+        let expected = qa.dequantize().matmul(&qb.dequantize());
+        let actual = qa.matmul(&qb);
+        for i in 0..8 {
+            for j in 0..6 {
+                assert!((expected[(i, j)] - actual[(i, j)]).abs() < 1e-4);
+            }
+        }
+
+        // requantization stays within one output step
+        let mut observer = Observer::new();
+        observer.observe(&actual);
+        let output = observer.params(scheme);
+
+        let requantized = qa.matmul_requantize(&qb, output).dequantize();
+        for i in 0..8 {
+            for j in 0..6 {
+                assert!((requantized[(i, j)] - actual[(i, j)]).abs() <= output.scale);
+            }
+        }
+    }
+}
+
+#[test]
+fn observer() {
+    let mut observer = Observer::new();
+    assert_eq!(observer.range(), None);
+
+    observer.observe(&Matrix::new(&[[1., -2.]]));
+    observer.observe(&Matrix::new(&[[4., 0.]]));
+    assert_eq!(observer.range(), Some((-2., 4.)));
+
+    let mut observer = Observer::moving_average(0.5);
+    observer.observe(&Matrix::new(&[[1., -2.]]));
+    observer.observe(&Matrix::new(&[[4., 0.]]));
+    assert_eq!(observer.range(), Some((-1., 2.5)));
+}
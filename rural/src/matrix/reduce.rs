@@ -0,0 +1,315 @@
+//! Reductions, norms and error metrics.
+//!
+//! Axis reductions keep the reduced axis with size `1`, so that the result broadcasts against the
+//! original matrix (e.g. `&a - &a.mean(Axis::Rows)` centers every column).
+
+use super::{Matrix, MatrixView};
+use crate::error::{Error, OrPanic, Result};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Axis {
+    /// Reduce over the rows: one value per column, shape `(1, n)`.
+    Rows,
+    /// Reduce over the columns: one value per row, shape `(m, 1)`.
+    Cols,
+}
+
+/// Vector norm, applied to the rows, the columns or all elements of a matrix.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Norm {
+    /// Sum of absolute values.
+    L1,
+    /// Euclidean norm (of all elements: the Frobenius norm).
+    #[default]
+    L2,
+    /// Largest absolute value.
+    Max,
+}
+
+impl Norm {
+    fn of(self, values: impl Iterator<Item = f32>) -> f32 {
+        match self {
+            Norm::L1 => values.map(f32::abs).sum(),
+            Norm::L2 => values.map(|x| x * x).sum::<f32>().sqrt(),
+            Norm::Max => values.fold(0., |acc, x| acc.max(x.abs())),
+        }
+    }
+}
+
+impl<'a> MatrixView<'a> {
+    fn elements(self) -> impl Iterator<Item = f32> + 'a {
+        let (m, n) = self.shape();
+        (0..m).flat_map(move |i| (0..n).map(move |j| self[(i, j)]))
+    }
+
+    // `f` of every row (`Axis::Cols`) or column (`Axis::Rows`), in the shape of the reduction
+    fn reduce(self, axis: Axis, f: impl Fn(&mut dyn Iterator<Item = f32>) -> f32) -> Matrix {
+        let (m, n) = self.shape();
+
+        match axis {
+            Axis::Rows => Matrix::from_fn(1, n, |_, j| f(&mut (0..m).map(|i| self[(i, j)]))),
+            Axis::Cols => Matrix::from_fn(m, 1, |i, _| f(&mut (0..n).map(|j| self[(i, j)]))),
+        }
+    }
+
+    pub fn sum(self, axis: Axis) -> Matrix {
+        self.reduce(axis, |values| values.sum())
+    }
+
+    /// `NaN` along empty axes.
+    pub fn mean(self, axis: Axis) -> Matrix {
+        let count = match axis {
+            Axis::Rows => self.shape().0,
+            Axis::Cols => self.shape().1,
+        };
+        self.reduce(axis, |values| values.sum::<f32>() / count as f32)
+    }
+
+    /// `-inf` along empty axes.
+    pub fn max(self, axis: Axis) -> Matrix {
+        self.reduce(axis, |values| values.fold(f32::NEG_INFINITY, f32::max))
+    }
+
+    /// Index of the (first) largest value in every column (`Axis::Rows`) or row (`Axis::Cols`);
+    /// `0` along empty axes.
+    pub fn argmax(self, axis: Axis) -> Box<[usize]> {
+        let argmax = |values: &mut dyn Iterator<Item = f32>| {
+            let mut best = (0, f32::NEG_INFINITY);
+            for (k, x) in values.enumerate() {
+                if x > best.1 {
+                    best = (k, x);
+                }
+            }
+            best.0 as f32
+        };
+
+        let indices = self.reduce(axis, argmax);
+        indices.data.iter().map(|&k| k as usize).collect()
+    }
+
+    /// Sum of all elements.
+    pub fn sum_all(self) -> f32 {
+        self.elements().sum()
+    }
+
+    /// Norm of all elements (i.e. of the flattened matrix).
+    pub fn norm(self, norm: Norm) -> f32 {
+        norm.of(self.elements())
+    }
+
+    pub fn frobenius_norm(self) -> f32 {
+        self.norm(Norm::L2)
+    }
+
+    /// Largest singular value, by power iteration on `AᵀA` (from a fixed starting vector).
+    pub fn spectral_norm(self) -> f32 {
+        let (_m, n) = self.shape();
+        if self.is_empty() {
+            return 0.;
+        }
+
+        let mut v = Matrix::random(n, 1, 0);
+        let mut sigma = 0f32;
+
+        for _ in 0..100 {
+            let norm = v.frobenius_norm();
+            if norm == 0. {
+                break;
+            }
+            v *= 1. / norm;
+
+            let u = self.matmul(&v);
+            let previous = sigma;
+            sigma = u.frobenius_norm();
+
+            if (sigma - previous).abs() <= 1e-6 * sigma {
+                break;
+            }
+            v = self.transpose().matmul(&u);
+        }
+
+        sigma
+    }
+
+    /// Norm of every row, in a `(m, 1)` matrix.
+    pub fn row_norms(self, norm: Norm) -> Matrix {
+        self.reduce(Axis::Cols, |values| norm.of(values))
+    }
+
+    /// Norm of every column, in a `(1, n)` matrix.
+    pub fn col_norms(self, norm: Norm) -> Matrix {
+        self.reduce(Axis::Rows, |values| norm.of(values))
+    }
+
+    // `other - self`, for matrices of the same shape
+    fn difference<'b>(self, other: impl Into<MatrixView<'b>>) -> Result<Matrix> {
+        let other = other.into();
+        if self.shape() != other.shape() {
+            return Err(Error::ShapeMismatch {
+                lhs: self.shape(),
+                rhs: other.shape(),
+            });
+        }
+
+        self.try_zip_with(other, |x, y| y - x)
+    }
+
+    /// Mean squared error of `other` (`NaN` if empty).
+    pub fn mean_squared_error<'b>(self, other: impl Into<MatrixView<'b>>) -> f32 {
+        self.try_mean_squared_error(other).or_panic()
+    }
+
+    pub fn try_mean_squared_error<'b>(self, other: impl Into<MatrixView<'b>>) -> Result<f32> {
+        let (m, n) = self.shape();
+        let error = self.difference(other)?.frobenius_norm();
+        Ok(error * error / (m * n) as f32)
+    }
+
+    /// Largest absolute elementwise error of `other`.
+    pub fn max_abs_error<'b>(self, other: impl Into<MatrixView<'b>>) -> f32 {
+        self.try_max_abs_error(other).or_panic()
+    }
+
+    pub fn try_max_abs_error<'b>(self, other: impl Into<MatrixView<'b>>) -> Result<f32> {
+        Ok(self.difference(other)?.norm(Norm::Max))
+    }
+
+    /// `|other - self|_F / |self|_F`, with `self` as the reference.
+    pub fn relative_error<'b>(self, other: impl Into<MatrixView<'b>>) -> f32 {
+        self.try_relative_error(other).or_panic()
+    }
+
+    pub fn try_relative_error<'b>(self, other: impl Into<MatrixView<'b>>) -> Result<f32> {
+        Ok(self.difference(other)?.frobenius_norm() / self.frobenius_norm())
+    }
+}
+
+impl Matrix {
+    pub fn sum(&self, axis: Axis) -> Matrix {
+        self.view().sum(axis)
+    }
+
+    pub fn mean(&self, axis: Axis) -> Matrix {
+        self.view().mean(axis)
+    }
+
+    pub fn max(&self, axis: Axis) -> Matrix {
+        self.view().max(axis)
+    }
+
+    pub fn argmax(&self, axis: Axis) -> Box<[usize]> {
+        self.view().argmax(axis)
+    }
+
+    pub fn sum_all(&self) -> f32 {
+        self.data.iter().sum()
+    }
+
+    pub fn norm(&self, norm: Norm) -> f32 {
+        norm.of(self.data.iter().copied())
+    }
+
+    pub fn frobenius_norm(&self) -> f32 {
+        self.norm(Norm::L2)
+    }
+
+    pub fn spectral_norm(&self) -> f32 {
+        self.view().spectral_norm()
+    }
+
+    pub fn row_norms(&self, norm: Norm) -> Matrix {
+        self.view().row_norms(norm)
+    }
+
+    pub fn col_norms(&self, norm: Norm) -> Matrix {
+        self.view().col_norms(norm)
+    }
+
+    pub fn mean_squared_error<'b>(&self, other: impl Into<MatrixView<'b>>) -> f32 {
+        self.view().mean_squared_error(other)
+    }
+
+    pub fn try_mean_squared_error<'b>(&self, other: impl Into<MatrixView<'b>>) -> Result<f32> {
+        self.view().try_mean_squared_error(other)
+    }
+
+    pub fn max_abs_error<'b>(&self, other: impl Into<MatrixView<'b>>) -> f32 {
+        self.view().max_abs_error(other)
+    }
+
+    pub fn try_max_abs_error<'b>(&self, other: impl Into<MatrixView<'b>>) -> Result<f32> {
+        self.view().try_max_abs_error(other)
+    }
+
+    pub fn relative_error<'b>(&self, other: impl Into<MatrixView<'b>>) -> f32 {
+        self.view().relative_error(other)
+    }
+
+    pub fn try_relative_error<'b>(&self, other: impl Into<MatrixView<'b>>) -> Result<f32> {
+        self.view().try_relative_error(other)
+    }
+}
+
+#[test]
+fn reductions() {
+    let a = Matrix::new(&[[1., -2., 3.], [4., 5., -6.]]);
+
+    assert_eq!(a.sum(Axis::Rows), Matrix::new(&[[5., 3., -3.]]));
+    assert_eq!(a.sum(Axis::Cols), Matrix::new(&[[2.], [3.]]));
+    assert_eq!(a.mean(Axis::Rows), Matrix::new(&[[2.5, 1.5, -1.5]]));
+    assert_eq!(a.max(Axis::Cols), Matrix::new(&[[3.], [5.]]));
+    assert_eq!(&*a.argmax(Axis::Rows), &[1, 1, 0]);
+    assert_eq!(&*a.argmax(Axis::Cols), &[2, 1]);
+    assert_eq!(a.sum_all(), 5.);
+
+    // views reduce the same way
+    assert_eq!(
+        a.transpose().sum(Axis::Cols),
+        Matrix::new(&[[5.], [3.], [-3.]])
+    );
+    assert_eq!(&*a.transpose().argmax(Axis::Rows), &[2, 1]);
+
+    // centered columns
+    let centered = &a - &a.mean(Axis::Rows);
+    assert_eq!(centered.sum(Axis::Rows), Matrix::zeroes(1, 3));
+}
+
+#[test]
+fn norms() {
+    let a = Matrix::new(&[[3., -4.], [0., 0.]]);
+
+    assert_eq!(a.norm(Norm::L1), 7.);
+    assert_eq!(a.frobenius_norm(), 5.);
+    assert_eq!(a.norm(Norm::Max), 4.);
+    assert_eq!(a.row_norms(Norm::L2), Matrix::new(&[[5.], [0.]]));
+    assert_eq!(a.col_norms(Norm::L1), Matrix::new(&[[3., 4.]]));
+    assert_eq!(a.view().norm(Norm::L1), 7.);
+
+    // rank 1: the spectral norm is the Frobenius norm
+    assert!((a.spectral_norm() - 5.).abs() < 1e-5);
+
+    let d = Matrix::from_fn(4, 4, |i, j| if i == j { [1., -7., 2., 3.][i] } else { 0. });
+    assert!((d.spectral_norm() - 7.).abs() < 1e-4);
+    assert!((d.transpose().spectral_norm() - 7.).abs() < 1e-4);
+    assert_eq!(Matrix::zeroes(3, 2).spectral_norm(), 0.);
+
+    // `|A|_2 <= |A|_F`
+    let a = Matrix::random(20, 30, 0);
+    assert!(a.spectral_norm() <= a.frobenius_norm());
+}
+
+#[test]
+fn metrics() {
+    let expected = Matrix::new(&[[1., 2.], [3., 4.]]);
+    let actual = Matrix::new(&[[1., 2.], [3., 6.]]);
+
+    assert_eq!(expected.mean_squared_error(&actual), 1.);
+    assert_eq!(expected.max_abs_error(&actual), 2.);
+    assert_eq!(expected.relative_error(&actual), 2. / 30f32.sqrt());
+    assert_eq!(expected.relative_error(&expected), 0.);
+
+    assert!(matches!(
+        expected.try_mean_squared_error(expected.slice(.., ..1)),
+        Err(Error::ShapeMismatch { .. })
+    ));
+}
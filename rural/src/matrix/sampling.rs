@@ -0,0 +1,340 @@
+//! Randomized matrix multiplication by column/row sampling.
+//! * 'Fast Monte Carlo Algorithms for Matrices I: Approximating Matrix Multiplication'
+//!   (Drineas, Kannan, Mahoney)
+//!
+//! `A·B` is the sum of the `n` outer products `A^(k)·B_(k)` (column `k` of `A` times row `k` of
+//! `B`); only `c = ceil(factor * n)` of them are evaluated. The 'optimal' sampling probabilities
+//! are `p_k = |A^(k)| |B_(k)| / Σ_j |A^(j)| |B_(j)|`.
+
+use super::{Matrix, MatrixView, Norm};
+use crate::error::{check_factor, check_matmul, OrPanic, Result};
+use rand_core::{RngCore, SeedableRng};
+use rand_distr::Distribution;
+
+/// Strategy used by [`Matrix::rand_matmul_with`] to pick the outer products to evaluate.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Sampling {
+    /// `c` i.i.d. draws (with replacement) from `p`, each rescaled by `1 / (c * p_k)`.
+    /// Unbiased.
+    WithReplacement,
+    /// Poisson sampling without replacement: index `k` is kept independently with probability
+    /// `q_k = min(1, λ p_k)`, where `λ` is chosen such that `Σ q_k = c`, and rescaled by `1 / q_k`.
+    /// Unbiased; evaluates `c` outer products on average.
+    WithoutReplacement,
+    /// Deterministically keeps the `c` indices with the largest `p_k`, without rescaling.
+    /// Biased, but exact at `factor = 1`.
+    #[default]
+    TopC,
+    /// `c` i.i.d. uniform draws, each rescaled by `n / c`. Unbiased.
+    Uniform,
+}
+
+// NOTE: all zeroes if either operand is zero (i.e. nothing to sample)
+pub(super) fn probabilities(a_col_norm: &[f32], b_row_norm: &[f32]) -> Box<[f32]> {
+    assert_eq!(a_col_norm.len(), b_row_norm.len());
+
+    let sum_norm = crate::math::inner_product(a_col_norm, b_row_norm, 0.);
+
+    a_col_norm
+        .iter()
+        .zip(b_row_norm)
+        .map(|(a, b)| if sum_norm > 0. { a * b / sum_norm } else { 0. })
+        .collect()
+}
+
+pub(super) fn num_samples(n: usize, factor: f32) -> usize {
+    debug_assert!((0. ..=1.).contains(&factor));
+    ((n as f32 * factor).ceil() as usize).min(n)
+}
+
+// indices of the `c` largest elements of `x` (in no particular order)
+pub(super) fn indices(x: &[f32], c: usize) -> Box<[usize]> {
+    let n = x.len();
+    let mut indices: Vec<usize> = (0..n).collect();
+
+    if c == 0 {
+        return Box::new([]);
+    }
+
+    // NOTE: partial selection instead of a full sort, i.e. O(n) instead of O(n log n)
+    indices.select_nth_unstable_by(n - c, |&i, &j| x[i].total_cmp(&x[j]));
+
+    indices.split_off(n - c).into_boxed_slice()
+}
+
+// draws `c` indices from the distribution `prob` (with replacement), accumulating `1 / (c * p_k)`
+// per index so that duplicates are only evaluated once
+fn draw(prob: &[f32], c: usize, rng: &mut impl RngCore) -> Vec<(usize, f32)> {
+    let cdf: Box<[f32]> = prob
+        .iter()
+        .scan(0., |acc, &p| {
+            *acc += p;
+            Some(*acc)
+        })
+        .collect();
+
+    let total = cdf.last().copied().unwrap_or(0.);
+    if total <= 0. {
+        return vec![];
+    }
+
+    let uniform = rand_distr::Uniform::new(0., total);
+
+    let mut scale = vec![0f32; prob.len()];
+    for _ in 0..c {
+        let u = uniform.sample(rng);
+        // skip over zero-probability entries with the same cumulative value
+        let t = cdf.partition_point(|&x| x <= u).min(prob.len() - 1);
+        scale[t] += 1. / (c as f32 * prob[t] / total);
+    }
+
+    scale
+        .into_iter()
+        .enumerate()
+        .filter(|&(_, s)| s > 0.)
+        .collect()
+}
+
+// inclusion probabilities `q_k = min(1, λ p_k)` with `Σ q_k = c` (capped entries are fixed at 1
+// and the remaining mass is redistributed until no new entries exceed 1)
+pub(super) fn inclusion_probabilities(prob: &[f32], c: usize) -> Box<[f32]> {
+    let n = prob.len();
+
+    // everything (that can be) is kept
+    if prob.iter().filter(|&&p| p > 0.).count() <= c {
+        return prob.iter().map(|&p| if p > 0. { 1. } else { 0. }).collect();
+    }
+
+    let mut capped = vec![false; n];
+    let mut q: Box<[f32]> = vec![0.; n].into_boxed_slice();
+
+    loop {
+        let num_capped = capped.iter().filter(|&&x| x).count();
+        let free_mass: f32 = (0..n).filter(|&k| !capped[k]).map(|k| prob[k]).sum();
+
+        let budget = c.saturating_sub(num_capped) as f32;
+        let lambda = if free_mass > 0. {
+            budget / free_mass
+        } else {
+            0.
+        };
+
+        let mut changed = false;
+        for k in 0..n {
+            q[k] = if capped[k] { 1. } else { lambda * prob[k] };
+            if !capped[k] && q[k] >= 1. {
+                capped[k] = true;
+                changed = true;
+            }
+        }
+
+        if !changed {
+            return q;
+        }
+    }
+}
+
+impl Sampling {
+    /// Picks the outer products to evaluate, as `(index, scale)` pairs.
+    pub(super) fn plan(self, prob: &[f32], c: usize, rng: &mut impl RngCore) -> Vec<(usize, f32)> {
+        let n = prob.len();
+
+        match self {
+            Sampling::WithReplacement => draw(prob, c, rng),
+            Sampling::WithoutReplacement => {
+                let uniform = rand_distr::Uniform::new(0., 1.);
+
+                inclusion_probabilities(prob, c)
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &q)| q > 0. && uniform.sample(rng) < q)
+                    .map(|(k, &q)| (k, 1. / q))
+                    .collect()
+            }
+            Sampling::TopC => indices(prob, c).iter().map(|&k| (k, 1.)).collect(),
+            Sampling::Uniform => draw(&vec![1. / n as f32; n], c, rng),
+        }
+    }
+}
+
+// Σ scale * A^(k)·B_(k) over the planned indices
+pub(super) fn outer_products(a: MatrixView, b: MatrixView, plan: &[(usize, f32)]) -> Matrix {
+    let (m, _n) = a.shape();
+    let (_n, p) = b.shape();
+
+    // TODO(toms): lift this allocation out?
+    let mut result = Matrix::zeroes(m, p);
+
+    for &(t, scale) in plan {
+        for i in 0..m {
+            let a = scale * a[(i, t)];
+            match b.row(t) {
+                Some(row) => {
+                    for (y, b) in result[i].iter_mut().zip(row) {
+                        *y += a * b;
+                    }
+                }
+                None => {
+                    for j in 0..p {
+                        result[(i, j)] += a * b[(t, j)];
+                    }
+                }
+            }
+        }
+    }
+
+    result
+}
+
+impl Matrix {
+    /// 'random' matrix multiplication (row-major order), deterministically keeping the
+    /// `ceil(factor * n)` most significant outer products (see [`Sampling::TopC`])
+    pub fn rand_matmul<'b>(&self, other: impl Into<MatrixView<'b>>, factor: f32) -> Matrix {
+        self.view().rand_matmul(other, factor)
+    }
+
+    pub fn try_rand_matmul<'b>(
+        &self,
+        other: impl Into<MatrixView<'b>>,
+        factor: f32,
+    ) -> Result<Matrix> {
+        self.view().try_rand_matmul(other, factor)
+    }
+
+    pub fn rand_matmul_with<'b>(
+        &self,
+        other: impl Into<MatrixView<'b>>,
+        factor: f32,
+        sampling: Sampling,
+        rng: &mut impl RngCore,
+    ) -> Matrix {
+        self.view().rand_matmul_with(other, factor, sampling, rng)
+    }
+
+    pub fn try_rand_matmul_with<'b>(
+        &self,
+        other: impl Into<MatrixView<'b>>,
+        factor: f32,
+        sampling: Sampling,
+        rng: &mut impl RngCore,
+    ) -> Result<Matrix> {
+        self.view()
+            .try_rand_matmul_with(other, factor, sampling, rng)
+    }
+}
+
+impl MatrixView<'_> {
+    pub fn rand_matmul<'b>(&self, other: impl Into<MatrixView<'b>>, factor: f32) -> Matrix {
+        self.try_rand_matmul(other, factor).or_panic()
+    }
+
+    pub fn try_rand_matmul<'b>(
+        &self,
+        other: impl Into<MatrixView<'b>>,
+        factor: f32,
+    ) -> Result<Matrix> {
+        // NOTE: `TopC` doesn't draw from the RNG
+        let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+        self.try_rand_matmul_with(other, factor, Sampling::TopC, &mut rng)
+    }
+
+    pub fn rand_matmul_with<'b>(
+        &self,
+        other: impl Into<MatrixView<'b>>,
+        factor: f32,
+        sampling: Sampling,
+        rng: &mut impl RngCore,
+    ) -> Matrix {
+        self.try_rand_matmul_with(other, factor, sampling, rng)
+            .or_panic()
+    }
+
+    pub fn try_rand_matmul_with<'b>(
+        &self,
+        other: impl Into<MatrixView<'b>>,
+        factor: f32,
+        sampling: Sampling,
+        rng: &mut impl RngCore,
+    ) -> Result<Matrix> {
+        let other = other.into();
+        check_matmul(self.shape(), other.shape())?;
+        check_factor(factor)?;
+
+        let (_m, n) = self.shape();
+
+        let prob = probabilities(
+            &self.col_norms(Norm::L2).data,
+            &other.row_norms(Norm::L2).data,
+        );
+        let c = num_samples(n, factor);
+
+        Ok(outer_products(*self, other, &sampling.plan(&prob, c, rng)))
+    }
+}
+
+#[test]
+fn unbiased_in_expectation() {
+    let a = Matrix::random(6, 12, 0);
+    let b = Matrix::random(12, 5, 1);
+    let expected = a.matmul(&b);
+
+    let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+
+    for sampling in [
+        Sampling::WithReplacement,
+        Sampling::WithoutReplacement,
+        Sampling::Uniform,
+    ] {
+        const N: usize = 4000;
+
+        let mut mean = Matrix::zeroes(6, 5);
+        for _ in 0..N {
+            mean += &a.rand_matmul_with(&b, 0.25, sampling, &mut rng);
+        }
+        mean *= 1. / N as f32;
+
+        let error = expected.relative_error(&mean);
+        assert!(error < 0.05, "{sampling:?}: relative error {error}");
+
+        // a single estimate is considerably worse than the average
+        let single = a.rand_matmul_with(&b, 0.25, sampling, &mut rng);
+        assert!(expected.relative_error(&single) > error);
+    }
+}
+
+#[test]
+fn exact_at_full_factor() {
+    let a = Matrix::random(4, 7, 0);
+    let b = Matrix::random(7, 3, 1);
+    let expected = a.matmul(&b);
+
+    let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+
+    assert!(expected.relative_error(&a.rand_matmul(&b, 1.)) < 1e-6);
+    assert!(
+        expected.relative_error(&a.rand_matmul_with(
+            &b,
+            1.,
+            Sampling::WithoutReplacement,
+            &mut rng
+        )) < 1e-6
+    );
+
+    // top-c keeps the largest terms as-is
+    let prob = probabilities(&a.col_norms(Norm::L2).data, &b.row_norms(Norm::L2).data);
+    let plan = Sampling::TopC.plan(&prob, 2, &mut rng);
+    assert_eq!(plan.len(), 2);
+    assert!(plan
+        .iter()
+        .all(|&(k, scale)| { scale == 1. && prob.iter().filter(|&&p| p > prob[k]).count() < 2 }));
+}
+
+#[test]
+fn inclusion_probabilities_sum_to_c() {
+    let q = inclusion_probabilities(&[0.7, 0.1, 0.1, 0.05, 0.05, 0.], 3);
+
+    assert_eq!(q[0], 1.);
+    assert_eq!(q[5], 0.);
+    assert!((q.iter().sum::<f32>() - 3.).abs() < 1e-5);
+    assert!(q.iter().all(|&q| (0. ..=1.).contains(&q)));
+}
@@ -0,0 +1,257 @@
+//! Approximate matrix multiplication by random projection of the inner dimension:
+//! `A·B ≈ (A·S)(Sᵀ·B)` for a random `(n, c)` matrix `S` with `E[S·Sᵀ] = I`.
+//! * 'Low Rank Approximation and Regression in Input Sparsity Time' (Clarkson, Woodruff) -
+//!   CountSketch
+//! * 'Improved Analysis of the Subsampled Randomized Hadamard Transform' (Tropp) - SRHT
+//!
+//! Unlike [`Matrix::rand_matmul`], every output depends on all `n` outer products, at the cost
+//! of computing the projections.
+
+use super::sampling::num_samples;
+use super::{Matrix, MatrixView};
+use crate::error::{check_factor, check_matmul, OrPanic, Result};
+use rand_core::RngCore;
+use rand_distr::{Distribution, Uniform};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Sketch {
+    /// Dense i.i.d. `N(0, 1/c)` entries; `O(n c)` per row/column of the operands.
+    Gaussian,
+    /// Each of the `n` rows of `S` has a single random `±1` in a random column; `O(n)` per
+    /// row/column of the operands.
+    #[default]
+    CountSketch,
+    /// `sqrt(n / c) D H R`: random signs, a normalized Hadamard transform (with `n` padded to a
+    /// power of two) and `c` columns sampled without replacement; `O(n log n)` per row/column.
+    Srht,
+}
+
+// `S`, in a form that can be applied to either operand
+enum Projection {
+    Gaussian(Matrix),
+    CountSketch {
+        buckets: Box<[usize]>,
+        signs: Box<[f32]>,
+        c: usize,
+    },
+    Srht {
+        signs: Box<[f32]>,
+        rows: Box<[usize]>,
+        padded: usize,
+    },
+}
+
+fn signs(n: usize, rng: &mut impl RngCore) -> Box<[f32]> {
+    (0..n)
+        .map(|_| if rng.next_u32() & 1 == 0 { 1. } else { -1. })
+        .collect()
+}
+
+// unnormalized fast Walsh-Hadamard transform along the rows of `x` (i.e. of every column)
+fn fwht(x: &mut Matrix) {
+    let (n, w) = x.shape();
+    debug_assert!(n.is_power_of_two());
+
+    let mut h = 1;
+    while h < n {
+        for i in (0..n).step_by(2 * h) {
+            for k in i..i + h {
+                let (lo, hi) = x.data.split_at_mut((k + h) * w);
+                for (a, b) in lo[k * w..(k + 1) * w].iter_mut().zip(&mut hi[..w]) {
+                    (*a, *b) = (*a + *b, *a - *b);
+                }
+            }
+        }
+        h *= 2;
+    }
+}
+
+impl Projection {
+    fn new(sketch: Sketch, n: usize, c: usize, rng: &mut impl RngCore) -> Self {
+        match sketch {
+            Sketch::Gaussian => {
+                Projection::Gaussian(Matrix::normal(n, c, 0., (1. / c as f32).sqrt(), rng))
+            }
+            Sketch::CountSketch => {
+                let buckets = Uniform::new(0, c);
+                Projection::CountSketch {
+                    buckets: (0..n).map(|_| buckets.sample(rng)).collect(),
+                    signs: signs(n, rng),
+                    c,
+                }
+            }
+            Sketch::Srht => {
+                let padded = n.next_power_of_two();
+
+                // partial Fisher-Yates shuffle
+                let mut rows: Vec<usize> = (0..padded).collect();
+                for i in 0..c.min(padded) {
+                    let j = Uniform::new(i, padded).sample(rng);
+                    rows.swap(i, j);
+                }
+                rows.truncate(c);
+
+                Projection::Srht {
+                    signs: signs(n, rng),
+                    rows: rows.into(),
+                    padded,
+                }
+            }
+        }
+    }
+
+    // `Sᵀ·x` for an `(n, w)` matrix `x`
+    fn apply(&self, x: MatrixView) -> Matrix {
+        let (n, w) = x.shape();
+
+        match self {
+            // NOTE: `matmul` is much faster with contiguous rows on the right
+            Projection::Gaussian(s) if x.is_contiguous() => s.transpose().matmul(x),
+            Projection::Gaussian(s) => s.transpose().matmul(&x.to_matrix()),
+            Projection::CountSketch { buckets, signs, c } => {
+                let mut result = Matrix::zeroes(*c, w);
+                for k in 0..n {
+                    let (row, sign) = (&mut result[buckets[k]], signs[k]);
+                    for (j, y) in row.iter_mut().enumerate() {
+                        *y += sign * x[(k, j)];
+                    }
+                }
+                result
+            }
+            Projection::Srht {
+                signs,
+                rows,
+                padded,
+            } => {
+                let mut t = Matrix::zeroes(*padded, w);
+                for k in 0..n {
+                    for j in 0..w {
+                        t[(k, j)] = signs[k] * x[(k, j)];
+                    }
+                }
+                fwht(&mut t);
+
+                // normalized Hadamard (`1 / sqrt(n)`) and sampling (`sqrt(n / c)`)
+                let scale = 1. / (rows.len() as f32).sqrt();
+
+                let mut result = Matrix::zeroes(rows.len(), w);
+                for (r, &k) in rows.iter().enumerate() {
+                    for (y, x) in result[r].iter_mut().zip(&t[k]) {
+                        *y = scale * x;
+                    }
+                }
+                result
+            }
+        }
+    }
+}
+
+impl Matrix {
+    /// Approximates `self·other` as `(self·S)(Sᵀ·other)`, with `S` projecting the inner dimension
+    /// down to `ceil(factor * n)`.
+    pub fn sketch_matmul<'b>(
+        &self,
+        other: impl Into<MatrixView<'b>>,
+        factor: f32,
+        sketch: Sketch,
+        rng: &mut impl RngCore,
+    ) -> Matrix {
+        self.view().sketch_matmul(other, factor, sketch, rng)
+    }
+
+    pub fn try_sketch_matmul<'b>(
+        &self,
+        other: impl Into<MatrixView<'b>>,
+        factor: f32,
+        sketch: Sketch,
+        rng: &mut impl RngCore,
+    ) -> Result<Matrix> {
+        self.view().try_sketch_matmul(other, factor, sketch, rng)
+    }
+}
+
+impl MatrixView<'_> {
+    pub fn sketch_matmul<'b>(
+        &self,
+        other: impl Into<MatrixView<'b>>,
+        factor: f32,
+        sketch: Sketch,
+        rng: &mut impl RngCore,
+    ) -> Matrix {
+        self.try_sketch_matmul(other, factor, sketch, rng)
+            .or_panic()
+    }
+
+    pub fn try_sketch_matmul<'b>(
+        &self,
+        other: impl Into<MatrixView<'b>>,
+        factor: f32,
+        sketch: Sketch,
+        rng: &mut impl RngCore,
+    ) -> Result<Matrix> {
+        let other = other.into();
+        check_matmul(self.shape(), other.shape())?;
+        check_factor(factor)?;
+
+        let ((m, n), (_, p)) = (self.shape(), other.shape());
+        let c = num_samples(n, factor);
+
+        // an empty sketch (like `rand_matmul` without samples)
+        if c == 0 {
+            return Ok(Matrix::zeroes(m, p));
+        }
+
+        let projection = Projection::new(sketch, n, c, rng);
+
+        // `A·S = (Sᵀ·Aᵀ)ᵀ`
+        let a = projection.apply(self.transpose());
+        let b = projection.apply(other);
+
+        a.transpose().try_matmul(&b)
+    }
+}
+
+#[test]
+fn unbiased_sketches() {
+    use rand_core::SeedableRng;
+
+    let a = Matrix::random(6, 40, 0);
+    let b = Matrix::random(40, 5, 1);
+    let expected = a.matmul(&b);
+
+    let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+
+    for sketch in [Sketch::Gaussian, Sketch::CountSketch, Sketch::Srht] {
+        const N: usize = 2000;
+
+        let mut mean = Matrix::zeroes(6, 5);
+        for _ in 0..N {
+            mean += &a.sketch_matmul(&b, 0.25, sketch, &mut rng);
+        }
+        mean *= 1. / N as f32;
+
+        let error = expected.relative_error(&mean);
+        assert!(error < 0.1, "{sketch:?}: {error}");
+
+        let empty = a.sketch_matmul(&b, 0., sketch, &mut rng);
+        assert_eq!(empty, Matrix::zeroes(6, 5), "{sketch:?}");
+    }
+}
+
+#[test]
+fn hadamard() {
+    use rand_core::SeedableRng;
+
+    let mut x = Matrix::new(&[[1.], [0.], [0.], [0.]]);
+    fwht(&mut x);
+    assert_eq!(x, Matrix::new(&[[1.], [1.], [1.], [1.]]));
+
+    // with `c = n`, the SRHT is orthogonal (`S·Sᵀ = I`), so the product is exact
+    let mut rng = rand_xoshiro::Xoshiro256PlusPlus::seed_from_u64(0);
+    let a = Matrix::random(3, 16, 0);
+    let b = Matrix::random(16, 4, 1);
+
+    let expected = a.matmul(&b);
+    let actual = a.sketch_matmul(&b, 1., Sketch::Srht, &mut rng);
+    assert!(expected.sub(&actual).data.iter().all(|x| x.abs() < 1e-4));
+}
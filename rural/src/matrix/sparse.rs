@@ -0,0 +1,544 @@
+//! Compressed sparse (CSR/CSC) matrices, obtained from a dense [`Matrix`] by magnitude pruning.
+//! * 'Learning both Weights and Connections for Efficient Neural Networks' (Han, et al.)
+//! * 'Accelerating Sparse Deep Neural Networks' (Mishra, et al.) - N:M structured sparsity
+
+use super::{Matrix, MatrixView};
+use crate::error::{check_matmul, Error, OrPanic, Result};
+
+/// Which elements of a dense matrix are kept when converting to a sparse one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pruning {
+    /// Keeps elements with `|x| >= threshold`.
+    Threshold(f32),
+    /// Drops the `ceil(sparsity * len)` elements with the smallest magnitude.
+    Sparsity(f32),
+    /// Keeps the `n` largest-magnitude elements in every group of `m` consecutive elements of a row
+    /// (e.g. 2:4).
+    Structured { n: usize, m: usize },
+}
+
+/// Compressed sparse row matrix.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsrMatrix {
+    shape: (usize, usize),
+    indptr: Box<[usize]>,
+    indices: Box<[usize]>,
+    values: Box<[f32]>,
+}
+
+/// Compressed sparse column matrix.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CscMatrix {
+    shape: (usize, usize),
+    indptr: Box<[usize]>,
+    indices: Box<[usize]>,
+    values: Box<[f32]>,
+}
+
+impl Pruning {
+    /// Mask of the elements to keep (row-major order).
+    pub fn mask<'a>(self, m: impl Into<MatrixView<'a>>) -> Box<[bool]> {
+        self.try_mask(m).or_panic()
+    }
+
+    pub fn try_mask<'a>(self, m: impl Into<MatrixView<'a>>) -> Result<Box<[bool]>> {
+        match self {
+            Pruning::Sparsity(sparsity) if !(0. ..=1.).contains(&sparsity) => {
+                return Err(Error::InvalidArgument(format!(
+                    "sparsity {sparsity} (not in [0, 1])"
+                )));
+            }
+            Pruning::Structured { n, m } if m == 0 || n > m => {
+                return Err(Error::InvalidArgument(format!("N:M pattern {n}:{m}")));
+            }
+            _ => {}
+        }
+
+        let m = m.into();
+        let (rows, cols) = m.shape();
+
+        let magnitude: Box<[f32]> = (0..rows)
+            .flat_map(|i| (0..cols).map(move |j| m[(i, j)].abs()))
+            .collect();
+
+        Ok(match self {
+            Pruning::Threshold(threshold) => magnitude
+                .iter()
+                .map(|&x| x >= threshold && x > 0.)
+                .collect(),
+            Pruning::Sparsity(sparsity) => {
+                let len = magnitude.len();
+                let drop = ((len as f32 * sparsity).ceil() as usize).min(len);
+
+                let mut order: Vec<usize> = (0..len).collect();
+                if drop > 0 && drop < len {
+                    order.select_nth_unstable_by(drop, |&i, &j| {
+                        magnitude[i].total_cmp(&magnitude[j])
+                    });
+                }
+
+                let mut mask = vec![true; len].into_boxed_slice();
+                for &i in &order[..drop] {
+                    mask[i] = false;
+                }
+                for (keep, &x) in mask.iter_mut().zip(magnitude.iter()) {
+                    *keep &= x > 0.;
+                }
+                mask
+            }
+            Pruning::Structured { n, m } => {
+                let mut mask = vec![false; magnitude.len()].into_boxed_slice();
+                for i in 0..rows {
+                    for start in (0..cols).step_by(m) {
+                        let group = i * cols + start..i * cols + (start + m).min(cols);
+
+                        let mut order: Vec<usize> = group.collect();
+                        order.sort_unstable_by(|&a, &b| magnitude[b].total_cmp(&magnitude[a]));
+
+                        for &k in order.iter().take(n) {
+                            mask[k] = magnitude[k] > 0.;
+                        }
+                    }
+                }
+                mask
+            }
+        })
+    }
+}
+
+impl Matrix {
+    /// Dense copy with the pruned elements set to zero.
+    pub fn prune(&self, pruning: Pruning) -> Matrix {
+        self.try_prune(pruning).or_panic()
+    }
+
+    pub fn try_prune(&self, pruning: Pruning) -> Result<Matrix> {
+        let mut result = self.clone();
+        for (x, keep) in result.data.iter_mut().zip(pruning.try_mask(self)?.iter()) {
+            if !keep {
+                *x = 0.;
+            }
+        }
+        Ok(result)
+    }
+
+    /// `self·other` for a sparse `other`.
+    pub fn matmul_csr(&self, other: &CsrMatrix) -> Matrix {
+        self.try_matmul_csr(other).or_panic()
+    }
+
+    pub fn try_matmul_csr(&self, other: &CsrMatrix) -> Result<Matrix> {
+        check_matmul(self.shape(), other.shape)?;
+
+        let (m, _n) = self.shape();
+        let (_, p) = other.shape;
+
+        let mut result = Matrix::zeroes(m, p);
+
+        for i in 0..m {
+            let row = &mut result.data[i * p..(i + 1) * p];
+            for (k, &a) in self[i].iter().enumerate() {
+                let (indices, values) = other.row(k);
+                for (&j, &b) in indices.iter().zip(values) {
+                    row[j] += a * b;
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// `self·other` for a sparse `other`.
+    pub fn matmul_csc(&self, other: &CscMatrix) -> Matrix {
+        self.try_matmul_csc(other).or_panic()
+    }
+
+    pub fn try_matmul_csc(&self, other: &CscMatrix) -> Result<Matrix> {
+        check_matmul(self.shape(), other.shape)?;
+
+        let (m, _n) = self.shape();
+        let (_, p) = other.shape;
+
+        let mut result = Matrix::zeroes(m, p);
+
+        for i in 0..m {
+            let a = &self[i];
+            for j in 0..p {
+                let (indices, values) = other.col(j);
+                result[(i, j)] = indices.iter().zip(values).map(|(&k, &b)| a[k] * b).sum();
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+// the arrays shared by CSR and CSC
+struct Compressed {
+    indptr: Box<[usize]>,
+    indices: Box<[usize]>,
+    values: Box<[f32]>,
+}
+
+// compresses along the 'outer' dimension (rows for CSR, columns for CSC)
+fn compress(outer: usize, inner: usize, get: impl Fn(usize, usize) -> (f32, bool)) -> Compressed {
+    let mut indptr = Vec::with_capacity(outer + 1);
+    let mut indices = vec![];
+    let mut values = vec![];
+
+    indptr.push(0);
+    for o in 0..outer {
+        for i in 0..inner {
+            let (value, keep) = get(o, i);
+            if keep {
+                indices.push(i);
+                values.push(value);
+            }
+        }
+        indptr.push(indices.len());
+    }
+
+    Compressed {
+        indptr: indptr.into(),
+        indices: indices.into(),
+        values: values.into(),
+    }
+}
+
+impl CsrMatrix {
+    pub fn from_dense<'a>(m: impl Into<MatrixView<'a>>, pruning: Pruning) -> Self {
+        Self::try_from_dense(m, pruning).or_panic()
+    }
+
+    pub fn try_from_dense<'a>(m: impl Into<MatrixView<'a>>, pruning: Pruning) -> Result<Self> {
+        let m = m.into();
+        let (rows, cols) = m.shape();
+        let mask = pruning.try_mask(m)?;
+
+        let Compressed {
+            indptr,
+            indices,
+            values,
+        } = compress(rows, cols, |i, j| (m[(i, j)], mask[i * cols + j]));
+
+        Ok(Self {
+            shape: (rows, cols),
+            indptr,
+            indices,
+            values,
+        })
+    }
+
+    pub fn shape(&self) -> (usize, usize) {
+        self.shape
+    }
+
+    /// Number of stored (non-zero) elements.
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Fraction of zero elements.
+    pub fn sparsity(&self) -> f32 {
+        let (m, n) = self.shape;
+        1. - self.nnz() as f32 / (m * n).max(1) as f32
+    }
+
+    /// Column indices and values of the non-zero elements in row `i`.
+    pub fn row(&self, i: usize) -> (&[usize], &[f32]) {
+        let range = self.indptr[i]..self.indptr[i + 1];
+        (&self.indices[range.clone()], &self.values[range])
+    }
+
+    /// Zero-copy transpose (the CSR arrays of `A` are the CSC arrays of `Aᵀ`).
+    pub fn transpose(self) -> CscMatrix {
+        let (m, n) = self.shape;
+
+        CscMatrix {
+            shape: (n, m),
+            indptr: self.indptr,
+            indices: self.indices,
+            values: self.values,
+        }
+    }
+
+    pub fn to_dense(&self) -> Matrix {
+        let (m, n) = self.shape;
+
+        let mut result = Matrix::zeroes(m, n);
+        for i in 0..m {
+            let (indices, values) = self.row(i);
+            for (&j, &x) in indices.iter().zip(values) {
+                result[(i, j)] = x;
+            }
+        }
+
+        result
+    }
+
+    /// Sparse-dense product `self·other`.
+    pub fn matmul<'b>(&self, other: impl Into<MatrixView<'b>>) -> Matrix {
+        self.try_matmul(other).or_panic()
+    }
+
+    pub fn try_matmul<'b>(&self, other: impl Into<MatrixView<'b>>) -> Result<Matrix> {
+        let other = other.into();
+        check_matmul(self.shape, other.shape())?;
+
+        let (m, _n) = self.shape;
+        let (_, p) = other.shape();
+
+        let mut result = Matrix::zeroes(m, p);
+
+        for i in 0..m {
+            let (indices, values) = self.row(i);
+            let row = &mut result[i];
+            for (&k, &a) in indices.iter().zip(values) {
+                match other.row(k) {
+                    Some(b) => {
+                        for (y, b) in row.iter_mut().zip(b) {
+                            *y += a * b;
+                        }
+                    }
+                    None => {
+                        for (j, y) in row.iter_mut().enumerate() {
+                            *y += a * other[(k, j)];
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Sparse matrix-vector product `y = self·x`.
+    pub fn gemv(&self, x: &[f32], y: &mut [f32]) {
+        self.try_gemv(x, y).or_panic()
+    }
+
+    pub fn try_gemv(&self, x: &[f32], y: &mut [f32]) -> Result<()> {
+        let (m, _n) = self.shape;
+        check_matmul(self.shape, (x.len(), 1))?;
+        if y.len() != m {
+            return Err(Error::ShapeMismatch {
+                lhs: (m, 1),
+                rhs: (y.len(), 1),
+            });
+        }
+
+        for (i, y) in y.iter_mut().enumerate() {
+            let (indices, values) = self.row(i);
+            *y = indices.iter().zip(values).map(|(&k, &a)| a * x[k]).sum();
+        }
+
+        Ok(())
+    }
+}
+
+impl CscMatrix {
+    pub fn from_dense<'a>(m: impl Into<MatrixView<'a>>, pruning: Pruning) -> Self {
+        Self::try_from_dense(m, pruning).or_panic()
+    }
+
+    pub fn try_from_dense<'a>(m: impl Into<MatrixView<'a>>, pruning: Pruning) -> Result<Self> {
+        let m = m.into();
+        let (rows, cols) = m.shape();
+        let mask = pruning.try_mask(m)?;
+
+        let Compressed {
+            indptr,
+            indices,
+            values,
+        } = compress(cols, rows, |j, i| (m[(i, j)], mask[i * cols + j]));
+
+        Ok(Self {
+            shape: (rows, cols),
+            indptr,
+            indices,
+            values,
+        })
+    }
+
+    pub fn shape(&self) -> (usize, usize) {
+        self.shape
+    }
+
+    /// Number of stored (non-zero) elements.
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Fraction of zero elements.
+    pub fn sparsity(&self) -> f32 {
+        let (m, n) = self.shape;
+        1. - self.nnz() as f32 / (m * n).max(1) as f32
+    }
+
+    /// Row indices and values of the non-zero elements in column `j`.
+    pub fn col(&self, j: usize) -> (&[usize], &[f32]) {
+        let range = self.indptr[j]..self.indptr[j + 1];
+        (&self.indices[range.clone()], &self.values[range])
+    }
+
+    /// Zero-copy transpose (the CSC arrays of `A` are the CSR arrays of `Aᵀ`).
+    pub fn transpose(self) -> CsrMatrix {
+        let (m, n) = self.shape;
+
+        CsrMatrix {
+            shape: (n, m),
+            indptr: self.indptr,
+            indices: self.indices,
+            values: self.values,
+        }
+    }
+
+    pub fn to_dense(&self) -> Matrix {
+        let (m, n) = self.shape;
+
+        let mut result = Matrix::zeroes(m, n);
+        for j in 0..n {
+            let (indices, values) = self.col(j);
+            for (&i, &x) in indices.iter().zip(values) {
+                result[(i, j)] = x;
+            }
+        }
+
+        result
+    }
+
+    /// Sparse-dense product `self·other`.
+    pub fn matmul<'b>(&self, other: impl Into<MatrixView<'b>>) -> Matrix {
+        self.try_matmul(other).or_panic()
+    }
+
+    pub fn try_matmul<'b>(&self, other: impl Into<MatrixView<'b>>) -> Result<Matrix> {
+        let other = other.into();
+        check_matmul(self.shape, other.shape())?;
+
+        let (m, n) = self.shape;
+        let (_n, p) = other.shape();
+
+        let mut result = Matrix::zeroes(m, p);
+
+        for k in 0..n {
+            let (indices, values) = self.col(k);
+            let b = other.row(k);
+            for (&i, &a) in indices.iter().zip(values) {
+                let row = &mut result[i];
+                match b {
+                    Some(b) => {
+                        for (y, b) in row.iter_mut().zip(b) {
+                            *y += a * b;
+                        }
+                    }
+                    None => {
+                        for (j, y) in row.iter_mut().enumerate() {
+                            *y += a * other[(k, j)];
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Sparse matrix-vector product `y = self·x`.
+    pub fn gemv(&self, x: &[f32], y: &mut [f32]) {
+        self.try_gemv(x, y).or_panic()
+    }
+
+    pub fn try_gemv(&self, x: &[f32], y: &mut [f32]) -> Result<()> {
+        let (m, _n) = self.shape;
+        check_matmul(self.shape, (x.len(), 1))?;
+        if y.len() != m {
+            return Err(Error::ShapeMismatch {
+                lhs: (m, 1),
+                rhs: (y.len(), 1),
+            });
+        }
+
+        y.fill(0.);
+        for (k, &x) in x.iter().enumerate() {
+            let (indices, values) = self.col(k);
+            for (&i, &a) in indices.iter().zip(values) {
+                y[i] += a * x;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn pruning() {
+    let a = Matrix::new(&[[1., -5., 0.5, 2.], [-3., 0., 4., -0.25]]);
+
+    assert_eq!(
+        a.prune(Pruning::Threshold(1.)),
+        Matrix::new(&[[1., -5., 0., 2.], [-3., 0., 4., 0.]])
+    );
+    assert_eq!(
+        a.prune(Pruning::Sparsity(0.5)),
+        Matrix::new(&[[0., -5., 0., 2.], [-3., 0., 4., 0.]])
+    );
+    assert_eq!(
+        a.prune(Pruning::Structured { n: 1, m: 2 }),
+        Matrix::new(&[[0., -5., 0., 2.], [-3., 0., 4., 0.]])
+    );
+    assert_eq!(
+        a.prune(Pruning::Structured { n: 2, m: 4 }),
+        Matrix::new(&[[0., -5., 0., 2.], [-3., 0., 4., 0.]])
+    );
+
+    let csr = CsrMatrix::from_dense(&a, Pruning::Threshold(0.));
+    assert_eq!(csr.nnz(), 7);
+    assert_eq!(csr.row(1), (&[0, 2, 3][..], &[-3., 4., -0.25][..]));
+}
+
+#[test]
+fn sparse_products() {
+    let a = Matrix::random(16, 24, 0);
+    let x = Matrix::random(24, 5, 1);
+    let w = Matrix::random(5, 16, 2);
+
+    let pruning = Pruning::Sparsity(0.75);
+    let dense = a.prune(pruning);
+
+    let csr = CsrMatrix::from_dense(&a, pruning);
+    let csc = CscMatrix::from_dense(&a, pruning);
+    assert_eq!(csr.to_dense(), dense);
+    assert_eq!(csc.to_dense(), dense);
+    assert_eq!(csr.nnz(), 96);
+    assert_eq!(csr.sparsity(), 0.75);
+    assert_eq!(
+        csr.clone().transpose().to_dense(),
+        dense.transpose().to_matrix()
+    );
+
+    let close = |a: &Matrix, b: &Matrix| a.sub(b).data.iter().all(|x| x.abs() < 1e-5);
+
+    let expected = dense.matmul(&x);
+    assert!(close(&csr.matmul(&x), &expected));
+    assert!(close(&csc.matmul(&x), &expected));
+
+    // strided `other`
+    let xt = x.transpose().to_matrix();
+    assert!(close(&csr.matmul(xt.transpose()), &expected));
+    assert!(close(&csc.matmul(xt.transpose()), &expected));
+
+    let expected = w.matmul(&dense);
+    assert!(close(&w.matmul_csr(&csr), &expected));
+    assert!(close(&w.matmul_csc(&csc), &expected));
+
+    let v = x.slice(.., 0..1).to_matrix();
+    let expected = dense.matmul(&v);
+    let (mut y0, mut y1) = ([0.; 16], [0.; 16]);
+    csr.gemv(&v.data, &mut y0);
+    csc.gemv(&v.data, &mut y1);
+    for i in 0..16 {
+        assert!((y0[i] - expected[(i, 0)]).abs() < 1e-5);
+        assert!((y1[i] - expected[(i, 0)]).abs() < 1e-5);
+    }
+}
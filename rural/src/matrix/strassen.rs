@@ -0,0 +1,192 @@
+//! Exact sub-cubic matrix multiplication (7 instead of 8 block products per level).
+//! * 'Gaussian Elimination is not Optimal' (Strassen)
+//! * Winograd's variant, which needs 15 instead of 18 block additions
+//!
+//! Blocks with a dimension at or below the cutoff use [`Matrix::matmul`]; odd dimensions are
+//! padded with a zero row/column at the level where they occur.
+//!
+//! NOTE: both are exact in exact arithmetic, but their floating point error grows with the
+//! recursion depth (normwise rather than elementwise stable).
+
+use super::{Matrix, MatrixView};
+use crate::error::{check_matmul, OrPanic, Result};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Variant {
+    #[default]
+    Strassen,
+    Winograd,
+}
+
+fn quadrants(m: MatrixView) -> [MatrixView; 4] {
+    let (r, c) = (m.shape().0 / 2, m.shape().1 / 2);
+
+    [
+        m.slice(..r, ..c),
+        m.slice(..r, c..),
+        m.slice(r.., ..c),
+        m.slice(r.., c..),
+    ]
+}
+
+fn pad(m: MatrixView, shape: (usize, usize)) -> Matrix {
+    let mut result = Matrix::zeroes(shape.0, shape.1);
+    result.slice_mut(..m.shape().0, ..m.shape().1).copy_from(m);
+    result
+}
+
+fn assemble(c11: &Matrix, c12: &Matrix, c21: &Matrix, c22: &Matrix) -> Matrix {
+    let (r, c) = c11.shape();
+
+    let mut result = Matrix::zeroes(2 * r, 2 * c);
+    result.slice_mut(..r, ..c).copy_from(c11);
+    result.slice_mut(..r, c..).copy_from(c12);
+    result.slice_mut(r.., ..c).copy_from(c21);
+    result.slice_mut(r.., c..).copy_from(c22);
+    result
+}
+
+fn multiply(a: MatrixView, b: MatrixView, cutoff: usize, variant: Variant) -> Matrix {
+    let (m, n) = a.shape();
+    let (_n, p) = b.shape();
+
+    if m.min(n).min(p) <= cutoff.max(1) {
+        return a.matmul(b);
+    }
+
+    if m % 2 == 1 || n % 2 == 1 || p % 2 == 1 {
+        let (m2, n2, p2) = (m + m % 2, n + n % 2, p + p % 2);
+        let (a, b) = (pad(a, (m2, n2)), pad(b, (n2, p2)));
+
+        return multiply(a.view(), b.view(), cutoff, variant)
+            .slice(..m, ..p)
+            .to_matrix();
+    }
+
+    let [a11, a12, a21, a22] = quadrants(a);
+    let [b11, b12, b21, b22] = quadrants(b);
+
+    let mul = |a: MatrixView, b: MatrixView| multiply(a, b, cutoff, variant);
+
+    match variant {
+        Variant::Strassen => {
+            let m1 = mul((a11 + a22).view(), (b11 + b22).view());
+            let m2 = mul((a21 + a22).view(), b11);
+            let m3 = mul(a11, (b12 - b22).view());
+            let m4 = mul(a22, (b21 - b11).view());
+            let m5 = mul((a11 + a12).view(), b22);
+            let m6 = mul((a21 - a11).view(), (b11 + b12).view());
+            let m7 = mul((a12 - a22).view(), (b21 + b22).view());
+
+            let mut c11 = &m1 + &m4;
+            c11 -= &m5;
+            c11 += &m7;
+
+            let mut c22 = &m1 - &m2;
+            c22 += &m3;
+            c22 += &m6;
+
+            assemble(&c11, &(&m3 + &m5), &(&m2 + &m4), &c22)
+        }
+        Variant::Winograd => {
+            let s1 = a21 + a22;
+            let s2 = &s1 - a11;
+            let s3 = a11 - a21;
+            let s4 = a12 - &s2;
+
+            let t1 = b12 - b11;
+            let t2 = b22 - &t1;
+            let t3 = b22 - b12;
+            let t4 = &t2 - b21;
+
+            let p1 = mul(a11, b11);
+            let p2 = mul(a12, b21);
+            let p3 = mul(s4.view(), b22);
+            let p4 = mul(a22, t4.view());
+            let p5 = mul(s1.view(), t1.view());
+            let p6 = mul(s2.view(), t2.view());
+            let p7 = mul(s3.view(), t3.view());
+
+            let u2 = &p1 + &p6;
+            let u3 = &u2 + &p7;
+            let u4 = &u2 + &p5;
+
+            assemble(&(&p1 + &p2), &(&u4 + &p3), &(&u3 - &p4), &(&u3 + &p5))
+        }
+    }
+}
+
+impl Matrix {
+    /// Strassen multiplication, falling back to [`Matrix::matmul`] for blocks with a dimension at
+    /// or below `cutoff`.
+    pub fn strassen<'b>(&self, other: impl Into<MatrixView<'b>>, cutoff: usize) -> Matrix {
+        self.view().fast_matmul(other, cutoff, Variant::Strassen)
+    }
+
+    /// Same as [`Matrix::strassen`], using Winograd's variant.
+    pub fn winograd<'b>(&self, other: impl Into<MatrixView<'b>>, cutoff: usize) -> Matrix {
+        self.view().fast_matmul(other, cutoff, Variant::Winograd)
+    }
+
+    pub fn fast_matmul<'b>(
+        &self,
+        other: impl Into<MatrixView<'b>>,
+        cutoff: usize,
+        variant: Variant,
+    ) -> Matrix {
+        self.view().fast_matmul(other, cutoff, variant)
+    }
+
+    pub fn try_fast_matmul<'b>(
+        &self,
+        other: impl Into<MatrixView<'b>>,
+        cutoff: usize,
+        variant: Variant,
+    ) -> Result<Matrix> {
+        self.view().try_fast_matmul(other, cutoff, variant)
+    }
+}
+
+impl MatrixView<'_> {
+    pub fn fast_matmul<'b>(
+        &self,
+        other: impl Into<MatrixView<'b>>,
+        cutoff: usize,
+        variant: Variant,
+    ) -> Matrix {
+        self.try_fast_matmul(other, cutoff, variant).or_panic()
+    }
+
+    pub fn try_fast_matmul<'b>(
+        &self,
+        other: impl Into<MatrixView<'b>>,
+        cutoff: usize,
+        variant: Variant,
+    ) -> Result<Matrix> {
+        let other = other.into();
+        check_matmul(self.shape(), other.shape())?;
+
+        Ok(multiply(*self, other, cutoff, variant))
+    }
+}
+
+#[test]
+fn fast_matmul() {
+    for (m, n, p) in [(16, 16, 16), (37, 21, 50), (64, 9, 33), (1, 40, 40)] {
+        let a = Matrix::random(m, n, 0);
+        let b = Matrix::random(n, p, 1);
+        let expected = a.matmul(&b);
+
+        for variant in [Variant::Strassen, Variant::Winograd] {
+            let actual = a.fast_matmul(&b, 2, variant);
+            assert_eq!(actual.shape(), (m, p));
+
+            let error = expected.max_abs_error(&actual);
+            assert!(error < 1e-4, "{variant:?} ({m}, {n}, {p}): {error}");
+        }
+    }
+
+    // below the cutoff, it's just `matmul`
+    let a = Matrix::random(8, 8, 0);
+    assert_eq!(a.strassen(&a, 8), a.matmul(&a));
+}
@@ -0,0 +1,481 @@
+use super::Matrix;
+use crate::error::{Error, OrPanic, Result};
+use core::fmt;
+use std::ops::{Bound, Index, IndexMut, Range, RangeBounds};
+
+/// Borrowed, strided window into the elements of a [`Matrix`].
+///
+/// Element `(i, j)` lives at `data[i * strides.0 + j * strides.1]`, which allows transposes and
+/// sub-matrices (e.g. the gate blocks of an LSTM weight matrix) without copying.
+#[derive(Clone, Copy)]
+pub struct MatrixView<'a> {
+    data: &'a [f32],
+    shape: (usize, usize),
+    strides: (usize, usize),
+}
+
+/// Mutable counterpart of [`MatrixView`].
+pub struct MatrixViewMut<'a> {
+    data: &'a mut [f32],
+    shape: (usize, usize),
+    strides: (usize, usize),
+}
+
+fn span((m, n): (usize, usize), (rs, cs): (usize, usize)) -> usize {
+    if m == 0 || n == 0 {
+        0
+    } else {
+        (m - 1) * rs + (n - 1) * cs + 1
+    }
+}
+
+fn bounds(range: impl RangeBounds<usize>, len: usize) -> Result<Range<usize>> {
+    let start = match range.start_bound() {
+        Bound::Included(&i) => i,
+        Bound::Excluded(&i) => i + 1,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&i) => i + 1,
+        Bound::Excluded(&i) => i,
+        Bound::Unbounded => len,
+    };
+
+    if start <= end && end <= len {
+        Ok(start..end)
+    } else {
+        Err(Error::OutOfBounds { start, end, len })
+    }
+}
+
+fn check_span(shape: (usize, usize), strides: (usize, usize), len: usize) -> Result<()> {
+    let end = span(shape, strides);
+    if end <= len {
+        Ok(())
+    } else {
+        Err(Error::OutOfBounds { start: 0, end, len })
+    }
+}
+
+// offset of the first element of the sub-matrix, and its shape
+fn sub(
+    shape: (usize, usize),
+    (rs, cs): (usize, usize),
+    rows: impl RangeBounds<usize>,
+    cols: impl RangeBounds<usize>,
+) -> Result<(usize, (usize, usize))> {
+    let rows = bounds(rows, shape.0)?;
+    let cols = bounds(cols, shape.1)?;
+
+    let shape = (rows.len(), cols.len());
+    let offset = if shape.0 == 0 || shape.1 == 0 {
+        0
+    } else {
+        rows.start * rs + cols.start * cs
+    };
+
+    Ok((offset, shape))
+}
+
+impl<'a> MatrixView<'a> {
+    pub fn new(data: &'a [f32], shape: (usize, usize), strides: (usize, usize)) -> Self {
+        Self::try_new(data, shape, strides).or_panic()
+    }
+
+    pub fn try_new(
+        data: &'a [f32],
+        shape: (usize, usize),
+        strides: (usize, usize),
+    ) -> Result<Self> {
+        check_span(shape, strides, data.len())?;
+
+        Ok(Self {
+            data,
+            shape,
+            strides,
+        })
+    }
+
+    pub fn shape(&self) -> (usize, usize) {
+        self.shape
+    }
+
+    pub fn strides(&self) -> (usize, usize) {
+        self.strides
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shape.0 == 0 || self.shape.1 == 0
+    }
+
+    /// Returns `true` if the rows are laid out back-to-back in memory (i.e. row-major order).
+    pub fn is_contiguous(&self) -> bool {
+        let (m, n) = self.shape;
+        (self.strides.1 == 1 || n <= 1) && (self.strides.0 == n || m <= 1)
+    }
+
+    pub fn transpose(self) -> MatrixView<'a> {
+        let (m, n) = self.shape;
+        let (rs, cs) = self.strides;
+
+        MatrixView {
+            data: self.data,
+            shape: (n, m),
+            strides: (cs, rs),
+        }
+    }
+
+    pub fn slice(self, rows: impl RangeBounds<usize>, cols: impl RangeBounds<usize>) -> Self {
+        self.try_slice(rows, cols).or_panic()
+    }
+
+    pub fn try_slice(
+        self,
+        rows: impl RangeBounds<usize>,
+        cols: impl RangeBounds<usize>,
+    ) -> Result<Self> {
+        let (offset, shape) = sub(self.shape, self.strides, rows, cols)?;
+
+        Ok(MatrixView {
+            data: &self.data[offset..][..span(shape, self.strides)],
+            shape,
+            strides: self.strides,
+        })
+    }
+
+    /// Returns the `i`-th row as a slice, if it is contiguous in memory.
+    pub fn row(&self, i: usize) -> Option<&'a [f32]> {
+        let (m, n) = self.shape;
+        assert!(i < m, "Row {i} out of bounds for {m} rows");
+
+        if self.strides.1 == 1 || n <= 1 {
+            Some(&self.data[i * self.strides.0..][..n])
+        } else {
+            None
+        }
+    }
+
+    pub fn to_matrix(&self) -> Matrix {
+        let (m, n) = self.shape;
+
+        Matrix {
+            data: (0..m)
+                .flat_map(|i| (0..n).map(move |j| self[(i, j)]))
+                .collect(),
+            shape: (m, n),
+        }
+    }
+}
+
+impl Index<(usize, usize)> for MatrixView<'_> {
+    type Output = f32;
+    fn index(&self, (i, j): (usize, usize)) -> &Self::Output {
+        let (m, n) = self.shape;
+        assert!(
+            i < m && j < n,
+            "Index {:?} out of bounds for shape {:?}",
+            (i, j),
+            (m, n)
+        );
+        &self.data[i * self.strides.0 + j * self.strides.1]
+    }
+}
+
+impl<'a> MatrixViewMut<'a> {
+    pub fn new(data: &'a mut [f32], shape: (usize, usize), strides: (usize, usize)) -> Self {
+        Self::try_new(data, shape, strides).or_panic()
+    }
+
+    pub fn try_new(
+        data: &'a mut [f32],
+        shape: (usize, usize),
+        strides: (usize, usize),
+    ) -> Result<Self> {
+        check_span(shape, strides, data.len())?;
+
+        Ok(Self {
+            data,
+            shape,
+            strides,
+        })
+    }
+
+    pub fn shape(&self) -> (usize, usize) {
+        self.shape
+    }
+
+    pub fn strides(&self) -> (usize, usize) {
+        self.strides
+    }
+
+    pub fn view(&self) -> MatrixView<'_> {
+        MatrixView {
+            data: self.data,
+            shape: self.shape,
+            strides: self.strides,
+        }
+    }
+
+    /// Reborrows this view for a shorter lifetime, so it can be sliced without being consumed.
+    pub fn reborrow(&mut self) -> MatrixViewMut<'_> {
+        MatrixViewMut {
+            data: self.data,
+            shape: self.shape,
+            strides: self.strides,
+        }
+    }
+
+    pub fn transpose(self) -> MatrixViewMut<'a> {
+        let (m, n) = self.shape;
+        let (rs, cs) = self.strides;
+
+        MatrixViewMut {
+            data: self.data,
+            shape: (n, m),
+            strides: (cs, rs),
+        }
+    }
+
+    pub fn slice(self, rows: impl RangeBounds<usize>, cols: impl RangeBounds<usize>) -> Self {
+        self.try_slice(rows, cols).or_panic()
+    }
+
+    pub fn try_slice(
+        self,
+        rows: impl RangeBounds<usize>,
+        cols: impl RangeBounds<usize>,
+    ) -> Result<Self> {
+        let (offset, shape) = sub(self.shape, self.strides, rows, cols)?;
+        let len = span(shape, self.strides);
+
+        Ok(MatrixViewMut {
+            data: &mut self.data[offset..][..len],
+            shape,
+            strides: self.strides,
+        })
+    }
+
+    pub fn fill(&mut self, value: f32) {
+        let (m, n) = self.shape;
+
+        for i in 0..m {
+            for j in 0..n {
+                self[(i, j)] = value;
+            }
+        }
+    }
+
+    pub fn copy_from<'b>(&mut self, other: impl Into<MatrixView<'b>>) {
+        self.try_copy_from(other).or_panic()
+    }
+
+    pub fn try_copy_from<'b>(&mut self, other: impl Into<MatrixView<'b>>) -> Result<()> {
+        let other = other.into();
+        if self.shape != other.shape {
+            return Err(Error::ShapeMismatch {
+                lhs: self.shape,
+                rhs: other.shape,
+            });
+        }
+
+        let (m, n) = self.shape;
+
+        for i in 0..m {
+            for j in 0..n {
+                self[(i, j)] = other[(i, j)];
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Index<(usize, usize)> for MatrixViewMut<'_> {
+    type Output = f32;
+    fn index(&self, (i, j): (usize, usize)) -> &Self::Output {
+        let (m, n) = self.shape;
+        assert!(
+            i < m && j < n,
+            "Index {:?} out of bounds for shape {:?}",
+            (i, j),
+            (m, n)
+        );
+        &self.data[i * self.strides.0 + j * self.strides.1]
+    }
+}
+impl IndexMut<(usize, usize)> for MatrixViewMut<'_> {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut Self::Output {
+        let (m, n) = self.shape;
+        assert!(
+            i < m && j < n,
+            "Index {:?} out of bounds for shape {:?}",
+            (i, j),
+            (m, n)
+        );
+        &mut self.data[i * self.strides.0 + j * self.strides.1]
+    }
+}
+
+impl<'a> From<&'a Matrix> for MatrixView<'a> {
+    fn from(m: &'a Matrix) -> Self {
+        m.view()
+    }
+}
+
+impl<'a> From<&'a MatrixView<'_>> for MatrixView<'a> {
+    fn from(v: &'a MatrixView<'_>) -> Self {
+        *v
+    }
+}
+
+impl<'a> From<&'a MatrixViewMut<'_>> for MatrixView<'a> {
+    fn from(v: &'a MatrixViewMut<'_>) -> Self {
+        v.view()
+    }
+}
+
+impl Matrix {
+    pub fn view(&self) -> MatrixView<'_> {
+        let (_, n) = self.shape;
+
+        MatrixView {
+            data: &self.data,
+            shape: self.shape,
+            strides: (n, 1),
+        }
+    }
+
+    pub fn view_mut(&mut self) -> MatrixViewMut<'_> {
+        let (_, n) = self.shape;
+
+        MatrixViewMut {
+            data: &mut self.data,
+            shape: self.shape,
+            strides: (n, 1),
+        }
+    }
+
+    pub fn transpose(&self) -> MatrixView<'_> {
+        self.view().transpose()
+    }
+
+    pub fn slice(
+        &self,
+        rows: impl RangeBounds<usize>,
+        cols: impl RangeBounds<usize>,
+    ) -> MatrixView<'_> {
+        self.view().slice(rows, cols)
+    }
+
+    pub fn try_slice(
+        &self,
+        rows: impl RangeBounds<usize>,
+        cols: impl RangeBounds<usize>,
+    ) -> Result<MatrixView<'_>> {
+        self.view().try_slice(rows, cols)
+    }
+
+    pub fn slice_mut(
+        &mut self,
+        rows: impl RangeBounds<usize>,
+        cols: impl RangeBounds<usize>,
+    ) -> MatrixViewMut<'_> {
+        self.view_mut().slice(rows, cols)
+    }
+
+    pub fn try_slice_mut(
+        &mut self,
+        rows: impl RangeBounds<usize>,
+        cols: impl RangeBounds<usize>,
+    ) -> Result<MatrixViewMut<'_>> {
+        self.view_mut().try_slice(rows, cols)
+    }
+}
+
+impl fmt::Debug for MatrixView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+
+        let (m, n) = self.shape;
+        for i in 0..m {
+            let row: Box<[f32]> = (0..n).map(|j| self[(i, j)]).collect();
+            list.entry(&row);
+        }
+
+        list.finish()
+    }
+}
+
+impl fmt::Debug for MatrixViewMut<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.view().fmt(f)
+    }
+}
+
+impl PartialEq for MatrixView<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        let (m, n) = self.shape;
+        self.shape == other.shape && (0..m).all(|i| (0..n).all(|j| self[(i, j)] == other[(i, j)]))
+    }
+}
+
+impl PartialEq<Matrix> for MatrixView<'_> {
+    fn eq(&self, other: &Matrix) -> bool {
+        *self == other.view()
+    }
+}
+
+#[test]
+fn transpose_and_slice() {
+    let a = Matrix::new(&[[1., 2., 3.], [4., 5., 6.]]);
+
+    let t = a.transpose();
+    assert_eq!(t.shape(), (3, 2));
+    assert_eq!(t, Matrix::new(&[[1., 4.], [2., 5.], [3., 6.]]));
+    assert_eq!(t.transpose(), a);
+
+    assert_eq!(a.slice(.., 1..), Matrix::new(&[[2., 3.], [5., 6.]]));
+    assert_eq!(a.slice(1.., ..2), Matrix::new(&[[4., 5.]]));
+    assert_eq!(t.slice(1..3, 1..), Matrix::new(&[[5.], [6.]]));
+    assert!(a.slice(2.., ..).is_empty());
+
+    assert!(a.view().is_contiguous());
+    assert!(!t.is_contiguous());
+    assert_eq!(a.slice(.., 1..).row(1), Some(&[5., 6.][..]));
+    assert_eq!(t.row(0), None);
+}
+
+#[test]
+fn mutable_slices() {
+    let mut a = Matrix::zeroes(3, 4);
+
+    a.slice_mut(1.., 2..).fill(1.);
+    a.view_mut()
+        .transpose()
+        .slice(3.., ..)
+        .copy_from(&Matrix::new(&[[7., 8., 9.]]));
+
+    assert_eq!(
+        a,
+        Matrix::new(&[[0., 0., 0., 7.], [0., 0., 1., 8.], [0., 0., 1., 9.]])
+    );
+}
+
+#[test]
+fn matmul_views() {
+    let a = Matrix::random(5, 8, 0);
+    let b = Matrix::random(5, 8, 1);
+
+    let expected = a.matmul(&b.transpose().to_matrix());
+    assert_eq!(a.matmul(b.transpose()), expected);
+
+    // multiply the first half of the inner dimension only
+    let expected = a
+        .slice(.., ..4)
+        .to_matrix()
+        .matmul(&b.transpose().slice(..4, ..).to_matrix());
+    assert_eq!(
+        a.slice(.., ..4).matmul(b.slice(.., ..4).transpose()),
+        expected
+    );
+}